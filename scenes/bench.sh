@@ -0,0 +1,22 @@
+#!/bin/bash
+# Renders a scene several times on a single thread and reports the
+# fastest and the median render time, e.g. ./bench.sh release cover.json 5
+
+build=${1:-release}
+scene=${2:-cover.json}
+runs=${3:-5}
+
+times=()
+for ((i = 0; i < runs; i++)); do
+    # Only the final line has the total time, progress goes to stderr.
+    output=$(../target/$build/traycer --threads 1 --scene-file $scene --out-file /tmp/bench.png 2>/dev/null \
+        | grep '^Rendered "' | tail -n 1)
+    if [ -z "$output" ]; then
+        echo "Rendering $scene failed" >&2
+        exit 1
+    fi
+    times+=($(echo "$output" | sed -E 's/.* in ([0-9]+) milliseconds.*/\1/'))
+done
+
+sorted=($(printf '%s\n' "${times[@]}" | sort -n))
+echo "$scene: ${runs} runs, fastest ${sorted[0]} ms, median ${sorted[$((runs / 2))]} ms"
@@ -0,0 +1,251 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SceneAnimation {
+    // Frames rendered when no range is given on the command line.
+    pub first_frame: Option<i64>,
+    pub last_frame: Option<i64>,
+    pub tracks: Vec<SceneTrack>,
+}
+
+// Animates one value of the scene, addressed by the keys and indices
+// leading to it separated by dots, e.g. "camera.from",
+// "lights.0.position", "materials.glass.transparency" or
+// "objects.ball.transformations.1.parameters".
+#[derive(Clone, Debug, Deserialize)]
+pub struct SceneTrack {
+    pub target: String,
+    pub keys: Vec<SceneKey>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SceneKey {
+    pub frame: f64,
+    pub value: SceneValue,
+    // How the value moves on to the next key, linear by default.
+    pub curve: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum SceneValue {
+    Number(f64),
+    Vector(Vec<f64>),
+}
+
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Curve {
+    Linear,
+    // Holds the value until the next key.
+    Step,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    // Catmull-Rom spline through the neighbouring keys, for paths that
+    // should not change direction abruptly at the keys.
+    Smooth,
+}
+
+impl Curve {
+    pub fn from_name(name: &str) -> Curve {
+        match name {
+            "linear" => Curve::Linear,
+            "step" => Curve::Step,
+            "ease-in" => Curve::EaseIn,
+            "ease-out" => Curve::EaseOut,
+            "ease-in-out" => Curve::EaseInOut,
+            "smooth" => Curve::Smooth,
+            _ => panic!("Undefined curve: {:?}!", name),
+        }
+    }
+
+    // Fraction of the way to the next key after the fraction t of the
+    // time has passed. Smooth curves are not a remapping of time, they
+    // are handled where the keys are known.
+    pub fn ease(&self, t: f64) -> f64 {
+        match self {
+            Curve::Linear | Curve::Smooth => t,
+            Curve::Step => 0.0,
+            Curve::EaseIn => t * t,
+            Curve::EaseOut => t * (2.0 - t),
+            Curve::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+
+fn components(value: &SceneValue) -> Vec<f64> {
+    match value {
+        SceneValue::Number(x) => vec![*x],
+        SceneValue::Vector(v) => v.clone(),
+    }
+}
+
+fn catmull_rom(p0: f64, p1: f64, p2: f64, p3: f64, t: f64) -> f64 {
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t * t
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t * t * t)
+}
+
+impl SceneTrack {
+    // Value of the track at the frame. Before the first and after the
+    // last key it keeps the value of that key.
+    pub fn value_at(&self, frame: f64) -> SceneValue {
+        let mut keys = self.keys.clone();
+        keys.sort_by(|a, b| a.frame.partial_cmp(&b.frame).unwrap());
+        let i = keys.iter().position(|k| k.frame > frame).unwrap_or(keys.len());
+        if i == 0 {
+            return keys[0].value.clone();
+        }
+        if i == keys.len() {
+            return keys[i - 1].value.clone();
+        }
+
+        let (a, b) = (&keys[i - 1], &keys[i]);
+        let curve = Curve::from_name(a.curve.as_ref().map_or("linear", |n| n.as_str()));
+        let t = curve.ease((frame - a.frame) / (b.frame - a.frame));
+
+        let p1 = components(&a.value);
+        let p2 = components(&b.value);
+        if p1.len() != p2.len() {
+            panic!("Keys of {:?} have values of different sizes!", self.target);
+        }
+        let p0 = if i >= 2 { components(&keys[i - 2].value) } else { p1.clone() };
+        let p3 = if i + 1 < keys.len() { components(&keys[i + 1].value) } else { p2.clone() };
+
+        let result: Vec<f64> = (0..p1.len()).map(|k| match curve {
+            Curve::Smooth if p0.len() == p1.len() && p3.len() == p1.len() =>
+                catmull_rom(p0[k], p1[k], p2[k], p3[k], t),
+            _ => p1[k] + (p2[k] - p1[k]) * t,
+        }).collect();
+        match a.value {
+            SceneValue::Number(_) => SceneValue::Number(result[0]),
+            SceneValue::Vector(_) => SceneValue::Vector(result),
+        }
+    }
+}
+
+impl SceneAnimation {
+    // The scene document with every track set to its value at the
+    // frame.
+    pub fn apply(&self, document: &Value, frame: f64) -> Value {
+        let mut result = document.clone();
+        for track in self.tracks.iter() {
+            let mut target = &mut result;
+            for key in track.target.split('.') {
+                let next = match target {
+                    Value::Object(map) => map.get_mut(key),
+                    Value::Array(list) => key.parse::<usize>().ok().and_then(move |i| list.get_mut(i)),
+                    _ => None,
+                };
+                target = next.unwrap_or_else(|| panic!("Undefined animation target: {:?}!", track.target));
+            }
+
+            let value = match track.value_at(frame) {
+                SceneValue::Number(x) => Value::from(x),
+                SceneValue::Vector(v) => Value::from(v),
+            };
+            if !(target.is_number() || target.is_array()) {
+                panic!("Only numbers and vectors can be animated: {:?}!", track.target);
+            }
+            *target = value;
+        }
+        result
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::equal;
+
+    fn track(curve: &str) -> SceneTrack {
+        SceneTrack {
+            target: "camera.from".to_string(),
+            keys: vec![
+                SceneKey { frame: 10.0, value: SceneValue::Vector(vec![4.0, 0.0]), curve: None },
+                SceneKey { frame: 0.0, value: SceneValue::Vector(vec![0.0, 0.0]), curve: Some(curve.to_string()) },
+                SceneKey { frame: 20.0, value: SceneValue::Vector(vec![4.0, 4.0]), curve: None },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_curves() {
+        for c in ["linear", "step", "ease-in", "ease-out", "ease-in-out", "smooth"].iter() {
+            let curve = Curve::from_name(c);
+            assert!(equal(curve.ease(1.0), if curve == Curve::Step { 0.0 } else { 1.0 }));
+            assert_eq!(curve.ease(0.0), 0.0);
+        }
+        assert!(Curve::EaseIn.ease(0.25) < 0.25);
+        assert!(Curve::EaseOut.ease(0.25) > 0.25);
+        assert_eq!(Curve::EaseInOut.ease(0.5), 0.5);
+    }
+
+    #[test]
+    fn test_value_at() {
+        let t = track("linear");
+        assert_eq!(t.value_at(-5.0), SceneValue::Vector(vec![0.0, 0.0]));
+        assert_eq!(t.value_at(2.5), SceneValue::Vector(vec![1.0, 0.0]));
+        assert_eq!(t.value_at(15.0), SceneValue::Vector(vec![4.0, 2.0]));
+        assert_eq!(t.value_at(30.0), SceneValue::Vector(vec![4.0, 4.0]));
+
+        assert_eq!(track("step").value_at(9.0), SceneValue::Vector(vec![0.0, 0.0]));
+
+        // The smooth curve overshoots towards the following key.
+        match track("smooth").value_at(5.0) {
+            SceneValue::Vector(v) => assert!(equal(v[0], 2.0) && v[1] < 0.0),
+            _ => panic!(),
+        }
+
+        let t = SceneTrack {
+            target: "camera.field_of_view".to_string(),
+            keys: vec![
+                SceneKey { frame: 0.0, value: SceneValue::Number(1.0), curve: Some("ease-in".to_string()) },
+                SceneKey { frame: 4.0, value: SceneValue::Number(2.0), curve: None },
+            ],
+        };
+        assert_eq!(t.value_at(2.0), SceneValue::Number(1.25));
+    }
+
+    #[test]
+    fn test_apply() {
+        let document: Value = serde_json::from_str(r#"{
+            "camera": {"from": [0.0, 0.0], "field_of_view": 1.0},
+            "lights": [{"position": [1.0, 2.0, 3.0]}],
+            "objects": {"ball": {"transformations": [{"parameters": [0.0, 0.0, 0.0]}]}}
+        }"#).unwrap();
+        let animation: SceneAnimation = serde_json::from_str(r#"{
+            "tracks": [
+                {"target": "camera.from", "keys": [
+                    {"frame": 0, "value": [0.0, 0.0]}, {"frame": 10, "value": [4.0, 0.0]}
+                ]},
+                {"target": "lights.0.position", "keys": [{"frame": 0, "value": [5.0, 5.0, 5.0]}]},
+                {"target": "objects.ball.transformations.0.parameters", "keys": [
+                    {"frame": 0, "value": [0.0, 0.0, 0.0]}, {"frame": 2, "value": [0.0, 2.0, 0.0]}
+                ]}
+            ]
+        }"#).unwrap();
+
+        let result = animation.apply(&document, 5.0);
+        assert_eq!(result["camera"]["from"], serde_json::json!([2.0, 0.0]));
+        assert_eq!(result["camera"]["field_of_view"], serde_json::json!(1.0));
+        assert_eq!(result["lights"][0]["position"], serde_json::json!([5.0, 5.0, 5.0]));
+        assert_eq!(result["objects"]["ball"]["transformations"][0]["parameters"], serde_json::json!([0.0, 2.0, 0.0]));
+        assert_eq!(document["camera"]["from"], serde_json::json!([0.0, 0.0]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Undefined animation target")]
+    fn test_undefined_target() {
+        let animation: SceneAnimation = serde_json::from_str(r#"{
+            "tracks": [{"target": "camera.nothing", "keys": [{"frame": 0, "value": 1.0}]}]
+        }"#).unwrap();
+        animation.apply(&serde_json::json!({"camera": {}}), 0.0);
+    }
+}
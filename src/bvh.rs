@@ -0,0 +1,181 @@
+use crate::ray::Ray;
+use crate::shapes::bounds::BoundingBox;
+use crate::shapes::shape::Shape;
+
+
+static MAX_LEAF_SIZE: usize = 4;
+
+
+pub enum BvhNode {
+    Leaf {
+        bounds: BoundingBox,
+        objects: Vec<(usize, BoundingBox)>,
+    },
+    Branch {
+        bounds: BoundingBox,
+        left: Box<BvhNode>,
+        right: Box<BvhNode>,
+    },
+}
+
+// Bounding volume hierarchy over a list of shapes. The hierarchy only
+// stores indices into the list it was built from, so it has to be
+// rebuilt whenever the list changes.
+pub struct Bvh {
+    pub root: Option<BvhNode>,
+    pub unbounded: Vec<usize>,
+}
+
+impl Bvh {
+    pub fn new(objects: &[Box<dyn Shape + Sync>]) -> Bvh {
+        let mut bounded = Vec::new();
+        let mut unbounded = Vec::new();
+
+        for (i, obj) in objects.iter().enumerate() {
+            let b = obj.bounds();
+            if b.is_finite() {
+                bounded.push((i, b));
+            } else {
+                unbounded.push(i);
+            }
+        }
+
+        let root = if bounded.is_empty() {
+            None
+        } else {
+            Some(build(bounded))
+        };
+
+        Bvh {
+            root: root,
+            unbounded: unbounded,
+        }
+    }
+
+    // Indices of all objects whose bounds are hit by the ray between
+    // t_min and t_max, in ascending order.
+    pub fn candidates(&self, r: &Ray, t_min: f64, t_max: f64) -> Vec<usize> {
+        let mut result = self.unbounded.clone();
+
+        let mut stack: Vec<&BvhNode> = Vec::new();
+        if let Some(root) = self.root.as_ref() {
+            stack.push(root);
+        }
+        while let Some(node) = stack.pop() {
+            match node {
+                BvhNode::Leaf { bounds, objects } => {
+                    if bounds.intersects(r, t_min, t_max) {
+                        for (i, b) in objects.iter() {
+                            if b.intersects(r, t_min, t_max) {
+                                result.push(*i);
+                            }
+                        }
+                    }
+                },
+                BvhNode::Branch { bounds, left, right } => {
+                    if bounds.intersects(r, t_min, t_max) {
+                        stack.push(left);
+                        stack.push(right);
+                    }
+                },
+            }
+        }
+
+        result.sort_unstable();
+        result
+    }
+}
+
+fn build(mut items: Vec<(usize, BoundingBox)>) -> BvhNode {
+    let mut bounds = BoundingBox::empty();
+    let mut centroids = BoundingBox::empty();
+    for (_, b) in items.iter() {
+        bounds = bounds.merge(b);
+        centroids.add_point(&b.centroid());
+    }
+
+    if items.len() <= MAX_LEAF_SIZE {
+        return BvhNode::Leaf {
+            bounds: bounds,
+            objects: items,
+        };
+    }
+
+    // Split at the median centroid along the axis with the largest
+    // centroid extent.
+    let extent = centroids.max - centroids.min;
+    let axis = if extent.x >= extent.y && extent.x >= extent.z {
+        0
+    } else if extent.y >= extent.z {
+        1
+    } else {
+        2
+    };
+    let key = |b: &BoundingBox| {
+        let c = b.centroid();
+        match axis {
+            0 => c.x,
+            1 => c.y,
+            _ => c.z,
+        }
+    };
+    items.sort_by(|a, b| key(&a.1).partial_cmp(&key(&b.1)).unwrap());
+
+    let right = items.split_off(items.len() / 2);
+    BvhNode::Branch {
+        bounds: bounds,
+        left: Box::new(build(items)),
+        right: Box::new(build(right)),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linalg::matrix4::Matrix4;
+    use crate::linalg::tuple::Tuple;
+    use crate::material::Material;
+    use crate::shapes::{plane::Plane, triangle::Triangle};
+
+    fn triangle_row(n: usize) -> Vec<Box<dyn Shape + Sync>> {
+        let mut result: Vec<Box<dyn Shape + Sync>> = Vec::new();
+        for i in 0..n {
+            let x = i as f64 * 2.0;
+            result.push(Box::new(Triangle::new(
+                Tuple::point(x, 1.0, 0.0),
+                Tuple::point(x - 1.0, 0.0, 0.0),
+                Tuple::point(x + 1.0, 0.0, 0.0),
+                Matrix4::identity(),
+                Material::default(),
+            )));
+        }
+        result
+    }
+
+    #[test]
+    fn test_candidates() {
+        let objects = triangle_row(20);
+        let bvh = Bvh::new(&objects);
+
+        let r = Ray::new(10.0, 0.5, -2.0, 0.0, 0.0, 1.0);
+        let c = bvh.candidates(&r, f64::NEG_INFINITY, f64::INFINITY);
+        assert_eq!(c, vec![5]);
+
+        let r = Ray::new(10.0, 5.0, -2.0, 0.0, 0.0, 1.0);
+        let c = bvh.candidates(&r, f64::NEG_INFINITY, f64::INFINITY);
+        assert!(c.is_empty());
+    }
+
+    #[test]
+    fn test_unbounded() {
+        let mut objects = triangle_row(10);
+        objects.push(Box::new(Plane::default()));
+        let bvh = Bvh::new(&objects);
+        assert_eq!(bvh.unbounded, vec![10]);
+
+        let r = Ray::new(100.0, 5.0, -2.0, 0.0, -1.0, 0.0);
+        let c = bvh.candidates(&r, f64::NEG_INFINITY, f64::INFINITY);
+        assert_eq!(c, vec![10]);
+    }
+}
@@ -1,22 +1,177 @@
-use crate::linalg::matrix::Matrix;
+use crate::color::Color;
+use crate::linalg::matrix4::Matrix4;
 use crate::linalg::tuple::Tuple;
+use crate::progress::{Progress, Reporting};
 use crate::ray::Ray;
+use crate::sampling::{hash_to_unit, ApertureShape, Filter, FilterSampler, SamplePattern};
+use crate::tiles::{self, TileOrder};
 use crate::utils::P0;
-use crate::world::World;
+use crate::world::{take_ray_count, World};
 
-use rayon::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use uuid::Uuid;
+
+
+// What a single ray sees: the color and the object hit.
+type Sample = (Color, Option<Uuid>);
+
+// Adaptive anti-aliasing, only pixels that differ from one of their
+// neighbours get more samples.
+#[derive(Debug, Copy, Clone)]
+pub struct Adaptive {
+    // Largest difference of any color channel between samples that are
+    // still considered equal, compared in the displayable range.
+    pub threshold: f64,
+    // How often a pixel may be split into quarters.
+    pub max_depth: u32,
+}
+
+impl Adaptive {
+    pub fn differ(&self, a: &Sample, b: &Sample) -> bool {
+        a.1 != b.1 || (0..3).any(|i| {
+            (a.0[i].clamp(0.0, 1.0) - b.0[i].clamp(0.0, 1.0)).abs() > self.threshold
+        })
+    }
+}
+
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Projection {
+    Perspective,
+    // Parallel rays, framing the same area at the focal distance as the
+    // perspective projection would.
+    Orthographic,
+    // Field of view across the larger image dimension, which may exceed
+    // 180 degrees. Pixels outside the image circle stay black. The
+    // equidistant mapping keeps angles, the equisolid one areas.
+    FisheyeEquidistant,
+    FisheyeEquisolid,
+    // Full 360 by 180 degree panorama, the image should be twice as wide
+    // as high.
+    Equirectangular,
+}
+
+impl Projection {
+    pub fn from_name(name: &str) -> Projection {
+        match name {
+            "perspective" => Projection::Perspective,
+            "orthographic" => Projection::Orthographic,
+            "fisheye" | "fisheye-equidistant" => Projection::FisheyeEquidistant,
+            "fisheye-equisolid" => Projection::FisheyeEquisolid,
+            "equirectangular" => Projection::Equirectangular,
+            _ => panic!("Undefined projection: {:?}!", name),
+        }
+    }
+}
+
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Convergence {
+    // The eyes share the focal plane, objects at the focal distance
+    // appear at screen depth.
+    OffAxis,
+    // Eyes looking straight ahead, everything appears in front of the
+    // screen.
+    Parallel,
+}
+
+impl Convergence {
+    pub fn from_name(name: &str) -> Convergence {
+        match name {
+            "off-axis" => Convergence::OffAxis,
+            "parallel" => Convergence::Parallel,
+            _ => panic!("Undefined convergence: {:?}!", name),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StereoLayout {
+    SideBySide,
+    TopBottom,
+    // Red channel from the left eye, green and blue from the right one.
+    Anaglyph,
+}
+
+impl StereoLayout {
+    pub fn from_name(name: &str) -> StereoLayout {
+        match name {
+            "side-by-side" => StereoLayout::SideBySide,
+            "top-bottom" => StereoLayout::TopBottom,
+            "anaglyph" => StereoLayout::Anaglyph,
+            _ => panic!("Undefined stereo layout: {:?}!", name),
+        }
+    }
+
+    pub fn combine(&self, left: Vec<Vec<Color>>, right: Vec<Vec<Color>>) -> Vec<Vec<Color>> {
+        match self {
+            StereoLayout::SideBySide => left.into_iter().zip(right)
+                .map(|(mut l, r)| {
+                    l.extend(r);
+                    l
+                }).collect(),
+            StereoLayout::TopBottom => left.into_iter().chain(right).collect(),
+            StereoLayout::Anaglyph => left.iter().zip(right.iter())
+                .map(|(l, r)| {
+                    l.iter().zip(r.iter()).map(|(a, b)| Color::new(a.r, b.g, b.b)).collect()
+                }).collect(),
+        }
+    }
+}
+
+// Renders a left and a right eye image, the eyes sit on the camera's
+// horizontal axis on either side of its position.
+#[derive(Debug, Copy, Clone)]
+pub struct Stereo {
+    pub interocular_distance: f64,
+    pub convergence: Convergence,
+    pub layout: StereoLayout,
+}
 
 
 pub struct Camera {
     pub hsize: i64,
     pub vsize: i64,
     pub field_of_view: f64,
-    pub transformation: Matrix,
-    pub inv_transformation: Matrix,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
     pub half_width: f64,
     pub half_height: f64,
     pub pixel_size: f64,
+    pub projection: Projection,
     origin: Tuple,
+
+    // Anti-aliasing, a single sample always goes through the pixel
+    // centre.
+    pub samples: usize,
+    pub sample_pattern: SamplePattern,
+    pub filter: FilterSampler,
+    pub adaptive: Option<Adaptive>,
+    extra_samples: AtomicUsize,
+
+    // Thin lens, an aperture radius of zero is a pinhole. Everything at
+    // the focal distance from the camera is in focus.
+    pub aperture: f64,
+    pub aperture_shape: ApertureShape,
+    pub focal_distance: f64,
+
+    // Rendering is split into tiles of tile_size pixels square, which
+    // are handed out to the threads in tile_order.
+    pub tile_size: usize,
+    pub tile_order: TileOrder,
+    // Where render progress goes, nowhere by default.
+    pub progress: Reporting,
+
+    // Motion blur, rays are spread over the time the shutter is open.
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+
+    pub stereo: Option<Stereo>,
+    // Offset of the eye being rendered along the camera space x axis.
+    eye: f64,
 }
 
 impl Camera {
@@ -33,7 +188,7 @@ impl Camera {
 
         let transformation = view_transform(from, to, up);
         let inv_transformation = transformation.invert();
-        let origin = inv_transformation.multiply_tuple(&P0);
+        let origin = inv_transformation * P0;
 
         Camera {
             hsize: hsize,
@@ -44,62 +199,358 @@ impl Camera {
             half_width: half_width,
             half_height: half_height,
             pixel_size: (half_width * 2.0) / (hsize as f64),
+            projection: Projection::Perspective,
             origin: origin,
+            samples: 1,
+            sample_pattern: SamplePattern::Jittered,
+            filter: FilterSampler::new(Filter::Box),
+            adaptive: None,
+            extra_samples: AtomicUsize::new(0),
+            aperture: 0.0,
+            aperture_shape: ApertureShape::Disk,
+            focal_distance: (*to - *from).magnitude(),
+            tile_size: 16,
+            tile_order: TileOrder::Scanline,
+            progress: Reporting::Quiet,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+            stereo: None,
+            eye: 0.0,
         }
     }
 
-    pub fn ray_for_pixel(&self, x: i64, y: i64) -> Ray {
-        let x_offset = (x as f64 + 0.5) * self.pixel_size;
-        let y_offset = (y as f64 + 0.5) * self.pixel_size;
+    // Copy of the camera rendering from an eye offset along the camera
+    // space x axis, which points to the left.
+    fn for_eye(&self, eye: f64) -> Camera {
+        Camera {
+            hsize: self.hsize,
+            vsize: self.vsize,
+            field_of_view: self.field_of_view,
+            transformation: self.transformation,
+            inv_transformation: self.inv_transformation,
+            half_width: self.half_width,
+            half_height: self.half_height,
+            pixel_size: self.pixel_size,
+            projection: self.projection,
+            origin: self.origin,
+            samples: self.samples,
+            sample_pattern: self.sample_pattern,
+            filter: self.filter.clone(),
+            adaptive: self.adaptive,
+            extra_samples: AtomicUsize::new(0),
+            aperture: self.aperture,
+            aperture_shape: self.aperture_shape,
+            focal_distance: self.focal_distance,
+            tile_size: self.tile_size,
+            tile_order: self.tile_order,
+            progress: self.progress,
+            shutter_open: self.shutter_open,
+            shutter_close: self.shutter_close,
+            stereo: self.stereo,
+            eye: eye,
+        }
+    }
+
+    pub fn ray_for_pixel(&self, x: i64, y: i64) -> Option<Ray> {
+        self.ray_through(x as f64 + 0.5, y as f64 + 0.5)
+    }
+
+    // Ray through a point on the image plane, in pixels from the top
+    // left corner. The point on the lens and the time are derived from
+    // the position, so the same point always gives the same ray.
+    pub fn ray_through(&self, px: f64, py: f64) -> Option<Ray> {
+        let key = px.to_bits() ^ py.to_bits().rotate_left(32);
+        let (lu, lv) = hash_to_unit(key);
+        self.lens_ray(px, py, lu, lv, hash_to_unit(!key).0)
+    }
+
+    // Ray through a point on the image plane and the point (lu, lv) of
+    // the unit square mapped onto the aperture, which only perspective
+    // projections use. lt is the fraction of the shutter interval that
+    // has passed. None outside of a fisheye's image circle.
+    pub fn lens_ray(&self, px: f64, py: f64, lu: f64, lv: f64, lt: f64) -> Option<Ray> {
+        let time = self.shutter_open + (self.shutter_close - self.shutter_open) * lt;
+        let x_offset = px * self.pixel_size;
+        let y_offset = py * self.pixel_size;
 
         let world_x = self.half_width - x_offset;
         let world_y = self.half_height - y_offset;
 
-        let w = Tuple::point(world_x, world_y, -1.0);
-        let pixel = self.inv_transformation.multiply_tuple(&w);
-        let direction = pixel.subtract(&self.origin).normalize();
+        // Origin and direction in camera space, looking down -z with x
+        // pointing to the left.
+        let (origin, direction) = match self.projection {
+            Projection::Perspective if self.aperture == 0.0 && self.eye == 0.0 => {
+                let w = Tuple::point(world_x, world_y, -1.0);
+                let pixel = self.inv_transformation * w;
+                let direction = (pixel - self.origin).normalize();
 
-        Ray {
-            origin: self.origin,
-            direction: direction,
-        }
+                return Some(Ray {
+                    origin: self.origin,
+                    direction: direction,
+                    time: time,
+                });
+            },
+            Projection::Perspective => {
+                // The pinhole ray hits the focal plane here, every ray
+                // from the lens through this pixel passes through the
+                // same point.
+                let d = self.focal_distance;
+                let mut focus = Tuple::point(world_x * d, world_y * d, -d);
+                let (lx, ly) = self.aperture_shape.sample(lu, lv);
+                let origin = Tuple::point(lx * self.aperture + self.eye, ly * self.aperture, 0.0);
+                // Off-axis eyes keep the focal plane, so their views are
+                // sheared rather than turned towards each other.
+                if self.stereo.is_some_and(|s| s.convergence == Convergence::Parallel) {
+                    focus += Tuple::vector(self.eye, 0.0, 0.0);
+                }
+                (origin, focus - origin)
+            },
+            Projection::Orthographic => {
+                let d = self.focal_distance;
+                (Tuple::point(world_x * d, world_y * d, 0.0), Tuple::vector(0.0, 0.0, -1.0))
+            },
+            Projection::FisheyeEquidistant | Projection::FisheyeEquisolid => {
+                let size = self.hsize.max(self.vsize) as f64;
+                let x = (self.hsize as f64 - 2.0 * px) / size;
+                let y = (self.vsize as f64 - 2.0 * py) / size;
+                let r = (x * x + y * y).sqrt();
+                if r > 1.0 {
+                    return None;
+                }
+
+                // Angle from the view direction.
+                let half_angle = self.field_of_view / 2.0;
+                let theta = match self.projection {
+                    Projection::FisheyeEquidistant => r * half_angle,
+                    _ => 2.0 * (r * (half_angle / 2.0).sin()).asin(),
+                };
+                let (sx, sy) = if r > 0.0 { (x / r, y / r) } else { (0.0, 0.0) };
+                (P0, Tuple::vector(theta.sin() * sx, theta.sin() * sy, -theta.cos()))
+            },
+            Projection::Equirectangular => {
+                let pi = std::f64::consts::PI;
+                let longitude = 2.0 * pi * (px / self.hsize as f64 - 0.5);
+                let latitude = pi * (0.5 - py / self.vsize as f64);
+                let direction = Tuple::vector(
+                    -latitude.cos() * longitude.sin(),
+                    latitude.sin(),
+                    -latitude.cos() * longitude.cos(),
+                );
+                (P0, direction)
+            },
+        };
+
+        // Other projections than perspective have no focal plane, their
+        // eyes are always parallel.
+        let origin = match self.projection {
+            Projection::Perspective => origin,
+            _ => origin + Tuple::vector(self.eye, 0.0, 0.0),
+        };
+        Some(Ray {
+            origin: self.inv_transformation * origin,
+            direction: (self.inv_transformation * direction).normalize(),
+            time: time,
+        })
     }
 
-    pub fn render(&self, world: &World) -> Vec<Vec<[u8; 3]>> {
-        let mut image = Vec::new();
-        for y in 0..self.vsize {
-            let row: Vec<[u8; 3]> = (0..self.hsize).into_par_iter()
-                .map(|x| {
-                    let ray = self.ray_for_pixel(x, y);
-                    world.color_at(&ray, 0).as_rgb()
-                }).collect();
-            image.push(row);
+    // What the ray sees, black if there is none.
+    fn trace(&self, world: &World, ray: Option<Ray>) -> Sample {
+        match ray {
+            Some(r) => world.trace(&r, 0),
+            None => (Color::black(), None),
         }
+    }
+
+    // Renders linear radiance, mapping it to displayable colors is left
+    // to the output stage.
+    pub fn render(&self, world: &World) -> Vec<Vec<Color>> {
+        // Every eye and every adaptive pass goes through all tiles.
+        let passes = if self.stereo.is_some() { 2 } else { 1 } * if self.adaptive.is_some() { 2 } else { 1 };
+        let tile_count = tiles::tiles(self.hsize as usize, self.vsize as usize, self.tile_size, self.tile_order).len();
+        let progress = Progress::new(self.progress, passes * tile_count);
+        take_ray_count();
+
+        let image = if let Some(stereo) = self.stereo {
+            let half = stereo.interocular_distance / 2.0;
+            let (left, right) = (self.for_eye(half), self.for_eye(-half));
+            let left_image = left.render_eye(world, &progress);
+            let right_image = right.render_eye(world, &progress);
+            self.extra_samples.store(left.extra_samples() + right.extra_samples(), Ordering::Relaxed);
+            stereo.layout.combine(left_image, right_image)
+        } else {
+            self.render_eye(world, &progress)
+        };
+        progress.finish();
         image
     }
+
+    fn render_eye(&self, world: &World, progress: &Progress) -> Vec<Vec<Color>> {
+        if self.adaptive.is_some() {
+            return self.render_adaptive(world, progress);
+        }
+
+        let image = self.render_tiles(progress, |x, y| self.color_at_pixel(world, x, y));
+        self.rows(image)
+    }
+
+    // Calls f for every pixel tile by tile, into a flat framebuffer.
+    fn render_tiles<T, F>(&self, progress: &Progress, f: F) -> Vec<T>
+    where
+        T: Clone + Send,
+        F: Fn(i64, i64) -> T + Sync,
+    {
+        tiles::render(self.hsize as usize, self.vsize as usize, self.tile_size, self.tile_order,
+                      |x, y| f(x as i64, y as i64), || progress.tile_done(take_ray_count()))
+    }
+
+    fn rows<T: Clone>(&self, framebuffer: Vec<T>) -> Vec<Vec<T>> {
+        if self.hsize == 0 {
+            return vec![vec![]; self.vsize as usize];
+        }
+        framebuffer.chunks(self.hsize as usize).map(|row| row.to_vec()).collect()
+    }
+
+    // Filtered average of samples around the pixel centre.
+    pub fn color_at_pixel(&self, world: &World, x: i64, y: i64) -> Color {
+        if self.samples <= 1 {
+            return self.trace(world, self.ray_for_pixel(x, y)).0;
+        }
+
+        let seed = (y * self.hsize + x) as u64;
+        let mut sum = Color::black();
+        let mut total_weight = 0.0;
+        // Lens and time samples are shuffled so they do not line up with
+        // the offsets on the image plane. Times are stratified over the
+        // shutter interval.
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut lens_samples = self.sample_pattern.samples(self.samples, !seed);
+        lens_samples.shuffle(&mut rng);
+        let n = lens_samples.len();
+        let mut time_samples: Vec<f64> = (0..n).map(|i| (i as f64 + rng.gen::<f64>()) / n as f64).collect();
+        time_samples.shuffle(&mut rng);
+        for (i, (u, v)) in self.sample_pattern.samples(self.samples, seed).into_iter().enumerate() {
+            let (dx, dy, weight) = self.filter.sample(u, v);
+            let (lu, lv) = lens_samples[i];
+            let ray = self.lens_ray(x as f64 + 0.5 + dx, y as f64 + 0.5 + dy, lu, lv, time_samples[i]);
+            sum += self.trace(world, ray).0 * weight;
+            total_weight += weight;
+        }
+
+        // At low sample counts the samples in negative lobes can outweigh
+        // the rest, the weighted average would then flip its sign. Even
+        // otherwise they can undershoot below black, which is clipped.
+        if total_weight <= 0.0 {
+            return self.trace(world, self.ray_for_pixel(x, y)).0;
+        }
+        let c = sum * (1.0 / total_weight);
+        Color::new(c.r.max(0.0), c.g.max(0.0), c.b.max(0.0))
+    }
+
+    // Samples spent by the last adaptive render beyond one per pixel.
+    pub fn extra_samples(&self) -> usize {
+        self.extra_samples.load(Ordering::Relaxed)
+    }
+
+    // Shoots one ray through every pixel centre first. Pixels that
+    // differ from a neighbour, in color or in the object hit, are then
+    // refined by sampling their corners and recursively splitting them
+    // into quarters where those still differ from the centre.
+    fn render_adaptive(&self, world: &World, progress: &Progress) -> Vec<Vec<Color>> {
+        let adaptive = self.adaptive.unwrap();
+
+        let first: Vec<Sample> = self.render_tiles(progress, |x, y| self.trace(world, self.ray_for_pixel(x, y)));
+        let at = |x: i64, y: i64| &first[(y * self.hsize + x) as usize];
+
+        let is_edge = |x: i64, y: i64| {
+            [(-1, 0), (1, 0), (0, -1), (0, 1)].iter().any(|&(dx, dy)| {
+                let (nx, ny) = (x + dx, y + dy);
+                nx >= 0 && ny >= 0 && nx < self.hsize && ny < self.vsize
+                    && adaptive.differ(at(x, y), at(nx, ny))
+            })
+        };
+
+        let refined: Vec<(Color, usize)> = self.render_tiles(progress, |x, y| {
+            let center = *at(x, y);
+            if !is_edge(x, y) {
+                return (center.0, 0);
+            }
+            let (fx, fy) = (x as f64, y as f64);
+            let corners = [(fx, fy), (fx + 1.0, fy), (fx, fy + 1.0), (fx + 1.0, fy + 1.0)]
+                .map(|(px, py)| self.trace(world, self.ray_through(px, py)));
+            let mut count = 4;
+            let color = self.refine(world, (fx, fy, 1.0), corners, center, adaptive.max_depth, &mut count);
+            (color, count)
+        });
+        self.extra_samples.store(refined.iter().map(|r| r.1).sum(), Ordering::Relaxed);
+        self.rows(refined.into_iter().map(|r| r.0).collect())
+    }
+
+    // Color of the square (x, y, size), given the samples at its corners
+    // (top left, top right, bottom left, bottom right) and centre.
+    fn refine(
+        &self,
+        world: &World,
+        square: (f64, f64, f64),
+        corners: [Sample; 4],
+        center: Sample,
+        depth: u32,
+        count: &mut usize
+    ) -> Color {
+        let adaptive = self.adaptive.unwrap();
+        if depth == 0 || corners.iter().all(|c| !adaptive.differ(c, &center)) {
+            let mut sum = center.0;
+            for c in corners.iter() {
+                sum += c.0;
+            }
+            return sum * 0.2;
+        }
+
+        let (x, y, size) = square;
+        let h = size / 2.0;
+        let trace = |px: f64, py: f64| self.trace(world, self.ray_through(px, py));
+        let top = trace(x + h, y);
+        let left = trace(x, y + h);
+        let right = trace(x + size, y + h);
+        let bottom = trace(x + h, y + size);
+        *count += 4;
+        let [tl, tr, bl, br] = corners;
+        let quarters = [
+            ((x, y, h), [tl, top, left, center]),
+            ((x + h, y, h), [top, tr, center, right]),
+            ((x, y + h, h), [left, center, bl, bottom]),
+            ((x + h, y + h, h), [center, right, bottom, br]),
+        ];
+
+        let mut sum = Color::black();
+        for (quarter, quarter_corners) in quarters.iter() {
+            let quarter_center = trace(quarter.0 + h / 2.0, quarter.1 + h / 2.0);
+            *count += 1;
+            sum += self.refine(world, *quarter, *quarter_corners, quarter_center, depth - 1, count);
+        }
+        sum * 0.25
+    }
 }
 
-pub fn view_transform(from: &Tuple, to: &Tuple, up: &Tuple) -> Matrix {
-    let forward = to.subtract(&from).normalize();
+pub fn view_transform(from: &Tuple, to: &Tuple, up: &Tuple) -> Matrix4 {
+    let forward = (*to - *from).normalize();
     let upn = up.normalize();
     let left = forward.cross(&upn);
     let true_up = left.cross(&forward);
 
-    let result = Matrix::from_values(
-        &vec![
-            vec![left.x, left.y, left.z, 0.0],
-            vec![true_up.x, true_up.y, true_up.z, 0.0],
-            vec![-forward.x, -forward.y, -forward.z, 0.0],
-            vec![0.0, 0.0, 0.0, 1.0],
-        ]
-    );
-    result.multiply_matrix(&Matrix::translation(-from.x, -from.y, -from.z))
+    let result = Matrix4::new([
+        [left.x, left.y, left.z, 0.0],
+        [true_up.x, true_up.y, true_up.z, 0.0],
+        [-forward.x, -forward.y, -forward.z, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+    result * Matrix4::translation(-from.x, -from.y, -from.z)
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::shapes::sphere::Sphere;
     use crate::utils::equal;
 
     #[test]
@@ -132,15 +583,234 @@ mod tests {
         let up = Tuple::vector(1.0, 1.0, 0.0);
         let m = view_transform(&from, &to, &up);
 
-        let expected = Matrix::from_values(
-            &vec![
-                vec![-0.50709, 0.50709,  0.67612, -2.36643],
-                vec![ 0.76771, 0.60609,  0.12121, -2.82842],
-                vec![-0.35857, 0.59761, -0.71714,  0.00000],
-                vec![ 0.00000, 0.00000,  0.00000,  1.00000],
-            ]
-        );
+        let expected = Matrix4::new([
+            [-0.50709, 0.50709,  0.67612, -2.36643],
+            [ 0.76771, 0.60609,  0.12121, -2.82842],
+            [-0.35857, 0.59761, -0.71714,  0.00000],
+            [ 0.00000, 0.00000,  0.00000,  1.00000],
+        ]);
         assert_eq!(m, expected);
     }
 
+    #[test]
+    fn test_color_at_pixel() {
+        let world = World::new(vec![Box::new(Sphere::default())], World::default().lights);
+        let mut cam = Camera::new(
+            11,
+            11,
+            std::f64::consts::PI / 2.0,
+            &Tuple::point(0.0, 0.0, -5.0),
+            &Tuple::point(0.0, 0.0, 0.0),
+            &Tuple::vector(0.0, 1.0, 0.0),
+        );
+        let center = world.color_at(&cam.ray_for_pixel(5, 5).unwrap(), 0);
+        assert_ne!(center, Color::black());
+        assert_eq!(cam.color_at_pixel(&world, 5, 5), center);
+
+        // A 2x2 grid with a box filter is the plain average of four rays.
+        cam.samples = 4;
+        cam.sample_pattern = SamplePattern::Grid;
+        let mut expected = Color::black();
+        for &(dx, dy) in [(-0.25, -0.25), (0.25, -0.25), (-0.25, 0.25), (0.25, 0.25)].iter() {
+            expected += world.color_at(&cam.ray_through(5.5 + dx, 5.5 + dy).unwrap(), 0) * 0.25;
+        }
+        assert_eq!(cam.color_at_pixel(&world, 5, 5), expected);
+    }
+
+    #[test]
+    fn test_mitchell_low_samples() {
+        let world = World::new(vec![Box::new(Sphere::default())], World::default().lights);
+        let mut cam = Camera::new(
+            21,
+            21,
+            std::f64::consts::PI / 3.0,
+            &Tuple::point(0.0, 0.0, -5.0),
+            &Tuple::point(0.0, 0.0, 0.0),
+            &Tuple::vector(0.0, 1.0, 0.0),
+        );
+        cam.filter = FilterSampler::new(Filter::Mitchell);
+        for samples in 2..=4 {
+            cam.samples = samples;
+            for row in cam.render(&world).iter() {
+                for c in row.iter() {
+                    assert!((0..3).all(|i| c[i] >= 0.0 && c[i].is_finite()), "{:?} with {} samples", c, samples);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty_render() {
+        let world = World::new(vec![Box::new(Sphere::default())], World::default().lights);
+        let from = Tuple::point(0.0, 0.0, -5.0);
+        let to = Tuple::point(0.0, 0.0, 0.0);
+        let up = Tuple::vector(0.0, 1.0, 0.0);
+        assert_eq!(Camera::new(0, 3, 1.0, &from, &to, &up).render(&world).len(), 3);
+        assert!(Camera::new(3, 0, 1.0, &from, &to, &up).render(&world).is_empty());
+    }
+
+    #[test]
+    fn test_adaptive() {
+        let world = World::new(vec![Box::new(Sphere::default())], World::default().lights);
+        let mut cam = Camera::new(
+            21,
+            21,
+            std::f64::consts::PI / 2.0,
+            &Tuple::point(0.0, 0.0, -5.0),
+            &Tuple::point(0.0, 0.0, 0.0),
+            &Tuple::vector(0.0, 1.0, 0.0),
+        );
+        let plain = cam.render(&world);
+
+        // Only pixels along the outline of the sphere, or where its
+        // shading changes quickly, are refined.
+        cam.adaptive = Some(Adaptive { threshold: 0.1, max_depth: 2 });
+        let image = cam.render(&world);
+        assert!(cam.extra_samples() > 0);
+        assert!(cam.extra_samples() < 21 * 21 * 4);
+        assert_eq!(image[0][0], plain[0][0]);
+
+        let mut refined = 0;
+        for y in 0..21 {
+            for x in 0..21 {
+                if image[y][x] != plain[y][x] {
+                    refined += 1;
+                }
+            }
+        }
+        assert!(refined > 0);
+    }
+
+    #[test]
+    fn test_lens_ray() {
+        let mut cam = Camera::new(
+            11,
+            11,
+            std::f64::consts::PI / 2.0,
+            &Tuple::point(0.0, 0.0, -5.0),
+            &Tuple::point(0.0, 0.0, 0.0),
+            &Tuple::vector(0.0, 1.0, 0.0),
+        );
+        assert_eq!(cam.focal_distance, 5.0);
+        let pinhole = cam.ray_through(2.5, 7.5).unwrap();
+        assert_eq!(cam.lens_ray(2.5, 7.5, 0.1, 0.9, 0.0).unwrap().origin, pinhole.origin);
+
+        // All rays through a pixel meet on the focal plane, z = 0 here,
+        // where the pinhole ray hits it.
+        cam.aperture = 0.5;
+        cam.aperture_shape = ApertureShape::Polygon(5);
+        let t = 5.0 / pinhole.direction.z;
+        let focus = pinhole.position(t);
+        for &(lu, lv) in [(0.1, 0.9), (0.5, 0.5), (0.7, 0.2)].iter() {
+            let r = cam.lens_ray(2.5, 7.5, lu, lv, 0.0).unwrap();
+            assert!((r.origin - pinhole.origin).magnitude() <= 0.5);
+            let t = -r.origin.z / r.direction.z;
+            assert_eq!(r.position(t), focus);
+        }
+        assert_ne!(cam.ray_through(2.5, 7.5).unwrap().origin, pinhole.origin);
+    }
+
+    #[test]
+    fn test_projections() {
+        let mut cam = Camera::new(
+            200,
+            100,
+            std::f64::consts::PI / 2.0,
+            &Tuple::point(0.0, 0.0, -5.0),
+            &Tuple::point(0.0, 0.0, 0.0),
+            &Tuple::vector(0.0, 1.0, 0.0),
+        );
+
+        cam.projection = Projection::Orthographic;
+        let r = cam.ray_through(100.0, 50.0).unwrap();
+        assert_eq!(r.origin, Tuple::point(0.0, 0.0, -5.0));
+        assert_eq!(r.direction, Tuple::vector(0.0, 0.0, 1.0));
+        // The left edge is the same as the perspective framing at the
+        // target.
+        let r = cam.ray_through(0.0, 50.0).unwrap();
+        assert_eq!(r.origin, Tuple::point(-5.0, 0.0, -5.0));
+        assert_eq!(r.direction, Tuple::vector(0.0, 0.0, 1.0));
+
+        cam.projection = Projection::FisheyeEquidistant;
+        cam.field_of_view = std::f64::consts::PI;
+        assert_eq!(cam.ray_through(100.0, 50.0).unwrap().direction, Tuple::vector(0.0, 0.0, 1.0));
+        assert_eq!(cam.ray_through(0.0, 50.0).unwrap().direction, Tuple::vector(-1.0, 0.0, 0.0));
+        assert_eq!(cam.ray_through(150.0, 50.0).unwrap().direction, Tuple::vector(0.5_f64.sqrt(), 0.0, 0.5_f64.sqrt()));
+        assert!(cam.ray_through(1.0, 1.0).is_none());
+
+        cam.projection = Projection::FisheyeEquisolid;
+        assert_eq!(cam.ray_through(200.0, 50.0).unwrap().direction, Tuple::vector(1.0, 0.0, 0.0));
+        let d = cam.ray_through(150.0, 50.0).unwrap().direction;
+        assert!(equal(d.x, (2.0 * (0.5_f64.sqrt() / 2.0).asin()).sin()));
+
+        cam.projection = Projection::Equirectangular;
+        assert_eq!(cam.ray_through(100.0, 50.0).unwrap().direction, Tuple::vector(0.0, 0.0, 1.0));
+        assert_eq!(cam.ray_through(150.0, 50.0).unwrap().direction, Tuple::vector(1.0, 0.0, 0.0));
+        assert_eq!(cam.ray_through(0.0, 50.0).unwrap().direction, Tuple::vector(0.0, 0.0, -1.0));
+        assert_eq!(cam.ray_through(100.0, 0.0).unwrap().direction, Tuple::vector(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_stereo() {
+        let mut cam = Camera::new(
+            11,
+            11,
+            std::f64::consts::PI / 2.0,
+            &Tuple::point(0.0, 0.0, -5.0),
+            &Tuple::point(0.0, 0.0, 0.0),
+            &Tuple::vector(0.0, 1.0, 0.0),
+        );
+        cam.stereo = Some(Stereo {
+            interocular_distance: 0.5,
+            convergence: Convergence::OffAxis,
+            layout: StereoLayout::SideBySide,
+        });
+
+        // Off-axis eyes both look through the focal point, the left eye
+        // sits on the left when looking down +z.
+        let left = cam.for_eye(0.25).ray_for_pixel(5, 5).unwrap();
+        let right = cam.for_eye(-0.25).ray_for_pixel(5, 5).unwrap();
+        assert_eq!(left.origin, Tuple::point(-0.25, 0.0, -5.0));
+        assert_eq!(right.origin, Tuple::point(0.25, 0.0, -5.0));
+        assert_eq!(left.position(5.0_f64.hypot(0.25)), Tuple::point(0.0, 0.0, 0.0));
+        assert_eq!(right.position(5.0_f64.hypot(0.25)), Tuple::point(0.0, 0.0, 0.0));
+
+        cam.stereo = cam.stereo.map(|s| Stereo { convergence: Convergence::Parallel, ..s });
+        let left = cam.for_eye(0.25).ray_for_pixel(5, 5).unwrap();
+        let right = cam.for_eye(-0.25).ray_for_pixel(5, 5).unwrap();
+        assert_eq!(left.origin, Tuple::point(-0.25, 0.0, -5.0));
+        assert_eq!(left.direction, Tuple::vector(0.0, 0.0, 1.0));
+        assert_eq!(right.direction, Tuple::vector(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_stereo_layout() {
+        let left = vec![vec![Color::new(0.1, 0.2, 0.3); 2]; 3];
+        let right = vec![vec![Color::new(0.4, 0.5, 0.6); 2]; 3];
+
+        let image = StereoLayout::SideBySide.combine(left.clone(), right.clone());
+        assert_eq!((image.len(), image[0].len()), (3, 4));
+        assert_eq!(image[1][1], left[1][1]);
+        assert_eq!(image[1][2], right[1][0]);
+
+        let image = StereoLayout::TopBottom.combine(left.clone(), right.clone());
+        assert_eq!((image.len(), image[0].len()), (6, 2));
+        assert_eq!(image[2][0], left[2][0]);
+        assert_eq!(image[3][0], right[0][0]);
+
+        let image = StereoLayout::Anaglyph.combine(left, right);
+        assert_eq!((image.len(), image[0].len()), (3, 2));
+        assert_eq!(image[0][0], Color::new(0.1, 0.5, 0.6));
+    }
+
+    #[test]
+    fn test_differ() {
+        let a = Adaptive { threshold: 0.1, max_depth: 1 };
+        let id = Some(Uuid::new_v4());
+        assert!(!a.differ(&(Color::new(0.5, 0.5, 0.5), id), &(Color::new(0.55, 0.45, 0.5), id)));
+        assert!(a.differ(&(Color::new(0.5, 0.5, 0.5), id), &(Color::new(0.5, 0.65, 0.5), id)));
+        assert!(a.differ(&(Color::black(), id), &(Color::black(), None)));
+        // Beyond the displayable range all highlights look the same.
+        assert!(!a.differ(&(Color::new(2.0, 1.0, 1.0), id), &(Color::new(9.0, 1.0, 1.0), id)));
+    }
 }
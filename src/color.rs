@@ -1,6 +1,11 @@
+use std::ops::{Add, AddAssign, Index, Mul, MulAssign, Sub};
+
 use crate::utils::equal;
 
 
+// Linear radiance. Components are not bounded, sums of several lights
+// or bright highlights may exceed 1.0 and are only mapped into the
+// displayable range by a tone mapping operator when writing the image.
 #[derive(Debug, Copy, Clone)]
 pub struct Color {
     pub r: f64,
@@ -11,20 +16,12 @@ pub struct Color {
 impl Color {
     pub fn new(r: f64, g: f64, b: f64) -> Color {
         Color {
-            r: r.clamp(0.0, 1.0),
-            g: g.clamp(0.0, 1.0),
-            b: b.clamp(0.0, 1.0)
+            r: r,
+            g: g,
+            b: b
         }
     }
 
-    pub fn as_rgb(&self) -> [u8; 3] {
-        [
-            (self.r * 255.0) as u8,
-            (self.g * 255.0) as u8,
-            (self.b * 255.0) as u8
-        ]
-    }
-
     pub fn from_vec(rgb: &Vec<f64>) -> Color {
         Color::new(rgb[0], rgb[1], rgb[2])
     }
@@ -36,46 +33,98 @@ impl Color {
     pub fn white() -> Color {
         Color::new(1.0, 1.0, 1.0)
     }
+}
+
+
+impl PartialEq for Color {
+    fn eq(&self, other: &Self) -> bool {
+        equal(self.r, other.r) &&
+            equal(self.g, other.g) &&
+            equal(self.b, other.b)
+    }
+}
+impl Eq for Color {}
+
 
-    pub fn add(&self, other: &Color) -> Color {
+impl Add for Color {
+    type Output = Color;
+
+    fn add(self, other: Color) -> Color {
         Color {
-            r: (self.r + other.r).clamp(0.0, 1.0),
-            g: (self.g + other.g).clamp(0.0, 1.0),
-            b: (self.b + other.b).clamp(0.0, 1.0)
+            r: self.r + other.r,
+            g: self.g + other.g,
+            b: self.b + other.b
         }
     }
+}
+
+impl Sub for Color {
+    type Output = Color;
 
-    pub fn subtract(&self, other: &Color) -> Color {
+    fn sub(self, other: Color) -> Color {
         Color {
-            r: (self.r - other.r).clamp(0.0, 1.0),
-            g: (self.g - other.g).clamp(0.0, 1.0),
-            b: (self.b - other.b).clamp(0.0, 1.0)
+            r: self.r - other.r,
+            g: self.g - other.g,
+            b: self.b - other.b
         }
     }
+}
+
+impl Mul<f64> for Color {
+    type Output = Color;
 
-    pub fn multiply(&self, n: f64) -> Color {
+    fn mul(self, n: f64) -> Color {
         Color {
-            r: (self.r * n).clamp(0.0, 1.0),
-            g: (self.g * n).clamp(0.0, 1.0),
-            b: (self.b * n).clamp(0.0, 1.0)
+            r: self.r * n,
+            g: self.g * n,
+            b: self.b * n
         }
     }
+}
+
+impl Mul<Color> for f64 {
+    type Output = Color;
+
+    fn mul(self, c: Color) -> Color {
+        c * self
+    }
+}
 
-    pub fn multiply_color(&self, &other: &Color) -> Color {
+// Component-wise (Hadamard) product, e.g. to filter light through a
+// surface color.
+impl Mul for Color {
+    type Output = Color;
+
+    fn mul(self, other: Color) -> Color {
         Color {
-            r: (self.r * other.r).clamp(0.0, 1.0),
-            g: (self.g * other.g).clamp(0.0, 1.0),
-            b: (self.b * other.b).clamp(0.0, 1.0)
+            r: self.r * other.r,
+            g: self.g * other.g,
+            b: self.b * other.b
         }
     }
 }
 
+impl AddAssign for Color {
+    fn add_assign(&mut self, other: Color) {
+        *self = *self + other;
+    }
+}
 
-impl PartialEq for Color {
-    fn eq(&self, other: &Self) -> bool {
-        equal(self.r, other.r) &&
-            equal(self.g, other.g) &&
-            equal(self.b, other.b)
+impl MulAssign<f64> for Color {
+    fn mul_assign(&mut self, n: f64) {
+        *self = *self * n;
+    }
+}
+
+impl Index<usize> for Color {
+    type Output = f64;
+
+    fn index(&self, i: usize) -> &f64 {
+        match i {
+            0 => &self.r,
+            1 => &self.g,
+            2 => &self.b,
+            _ => panic!("Color index out of range: {:?}!", i),
+        }
     }
 }
-impl Eq for Color {}
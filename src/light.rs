@@ -19,7 +19,17 @@ impl PointLight {
         }
     }
 
-    pub fn lighting<'a>(&self, object: &'a (dyn Shape + Sync), pos: &Tuple, is_shadowed: bool, eyev: &Tuple, normv: &Tuple) -> Color {
+    // Patterns are looked up at pattern_pos, which differs from pos for
+    // moving objects: they are patterned where they were built.
+    pub fn lighting<'a>(
+        &self,
+        object: &'a (dyn Shape + Sync),
+        pos: &Tuple,
+        pattern_pos: &Tuple,
+        is_shadowed: bool,
+        eyev: &Tuple,
+        normv: &Tuple
+    ) -> Color {
         // Determine base color depending on object color/pattern and
         // ambient light.
         let material = object.get_material();
@@ -27,29 +37,28 @@ impl PointLight {
         if let Some(c) = material.color {
             color = c;
         } else if let Some(p) = material.pattern.as_ref() {
-            color = p.color_at(object, pos);
+            color = p.color_at(object, pattern_pos);
         }
-        let effective_color = color.multiply_color(&self.intensity);
-        color = effective_color.multiply(material.ambient);
+        let effective_color = color * self.intensity;
+        color = effective_color * material.ambient;
 
 
         // Check if light is on the side of the surface.
-        let lightv = self.position.subtract(&pos).normalize();
+        let lightv = (self.position - *pos).normalize();
         let light_dot_normal = lightv.dot(&normv);
         if light_dot_normal >= 0.0 && !is_shadowed {
             // If so, and the surface is not shadowed, compute diffuse
             // and specular light as well.
-            let diffuse = effective_color.multiply(material.diffuse)
-                .multiply(light_dot_normal);
+            let diffuse = effective_color * material.diffuse * light_dot_normal;
 
-            let reflectv = ray::reflect(&lightv.negate(), &normv);
+            let reflectv = ray::reflect(&-lightv, &normv);
             let reflect_dot_eye = reflectv.dot(&eyev);
             let mut specular = Color::black();
             if reflect_dot_eye > 0.0 {
                 let factor = reflect_dot_eye.powf(material.shininess);
-                specular = self.intensity.multiply(material.specular).multiply(factor);
+                specular = self.intensity * material.specular * factor;
             }
-            color = color.add(&diffuse).add(&specular);
+            color = color + diffuse + specular;
         }
 
         color
@@ -75,7 +84,7 @@ mod tests {
             position: Tuple::point(0.0, 0.0, -10.0),
         };
         let s = Sphere::default();
-        let result = light.lighting(&s, &pos, false, &eyev, &normv);
+        let result = light.lighting(&s, &pos, &pos, false, &eyev, &normv);
         assert_eq!(result, Color::new(1.9, 1.9, 1.9));
 
         let eyev = Tuple::vector(0.0, 2.0_f64.sqrt() / 2.0, -2.0_f64.sqrt() / 2.0);
@@ -85,7 +94,7 @@ mod tests {
             position: Tuple::point(0.0, 0.0, -10.0),
         };
         let s = Sphere::default();
-        let result = light.lighting(&s, &pos, false, &eyev, &normv);
+        let result = light.lighting(&s, &pos, &pos, false, &eyev, &normv);
         assert_eq!(result, Color::new(1.0, 1.0, 1.0));
 
         let eyev = Tuple::vector(0.0, 0.0, -1.0);
@@ -95,7 +104,7 @@ mod tests {
             position: Tuple::point(0.0, 10.0, -10.0),
         };
         let s = Sphere::default();
-        let result = light.lighting(&s, &pos, false, &eyev, &normv);
+        let result = light.lighting(&s, &pos, &pos, false, &eyev, &normv);
         assert_eq!(result, Color::new(0.7364, 0.7364, 0.7364));
 
         let eyev = Tuple::vector(0.0, -2.0_f64.sqrt() / 2.0, -2.0_f64.sqrt() / 2.0);
@@ -105,7 +114,7 @@ mod tests {
             position: Tuple::point(0.0, 10.0, -10.0),
         };
         let s = Sphere::default();
-        let result = light.lighting(&s, &pos, false, &eyev, &normv);
+        let result = light.lighting(&s, &pos, &pos, false, &eyev, &normv);
         assert_eq!(result, Color::new(1.6364, 1.6364, 1.6364));
 
         let eyev = Tuple::vector(0.0, 0.0, -1.0);
@@ -115,7 +124,7 @@ mod tests {
             position: Tuple::point(0.0, 0.0, 10.0),
         };
         let s = Sphere::default();
-        let result = light.lighting(&s, &pos, false, &eyev, &normv);
+        let result = light.lighting(&s, &pos, &pos, false, &eyev, &normv);
         assert_eq!(result, Color::new(0.1, 0.1, 0.1));
     }
 }
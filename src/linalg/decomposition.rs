@@ -0,0 +1,117 @@
+use crate::linalg::matrix4::Matrix4;
+use crate::linalg::quaternion::Quaternion;
+use crate::linalg::tuple::Tuple;
+
+
+// Affine transformation split into translation * rotation * stretch,
+// where the stretch holds scaling and shearing. Interpolating the parts
+// separately keeps rotating objects rigid, blending the matrices would
+// shrink them halfway through a turn.
+#[derive(Debug, Copy, Clone)]
+pub struct Decomposition {
+    pub translation: Tuple,
+    pub rotation: Quaternion,
+    pub stretch: Matrix4,
+}
+
+impl Decomposition {
+    pub fn new(m: &Matrix4) -> Decomposition {
+        let translation = Tuple::vector(m[0][3], m[1][3], m[2][3]);
+        let mut linear = *m;
+        for i in 0..3 {
+            linear[i][3] = 0.0;
+        }
+
+        // Polar decomposition by averaging the matrix with its inverse
+        // transpose until it is orthonormal. A mirroring transformation
+        // is flipped first, it cannot be a rotation, so the mirroring
+        // ends up in the stretch.
+        let mut rotation = linear;
+        if linear.determinant() < 0.0 {
+            rotation = rotation * Matrix4::scaling(-1.0, -1.0, -1.0);
+        }
+        for _ in 0..100 {
+            let inverse = rotation.invert().transpose();
+            let mut change: f64 = 0.0;
+            for i in 0..3 {
+                for j in 0..3 {
+                    let next = 0.5 * (rotation[i][j] + inverse[i][j]);
+                    change = change.max((next - rotation[i][j]).abs());
+                    rotation[i][j] = next;
+                }
+            }
+            if change < 1e-12 {
+                break;
+            }
+        }
+
+        Decomposition {
+            translation: translation,
+            rotation: Quaternion::from_rotation(&rotation),
+            stretch: rotation.transpose() * linear,
+        }
+    }
+
+    pub fn to_matrix(self) -> Matrix4 {
+        let t = self.translation;
+        Matrix4::translation(t.x, t.y, t.z) * self.rotation.to_matrix() * self.stretch
+    }
+
+    // Transformation a fraction t of the way to the other one.
+    pub fn interpolate(&self, other: &Decomposition, t: f64) -> Decomposition {
+        let mut stretch = self.stretch;
+        for i in 0..3 {
+            for j in 0..3 {
+                stretch[i][j] += (other.stretch[i][j] - self.stretch[i][j]) * t;
+            }
+        }
+        Decomposition {
+            translation: self.translation + (other.translation - self.translation) * t,
+            rotation: self.rotation.slerp(&other.rotation, t),
+            stretch: stretch,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decomposition() {
+        let transformations = [
+            Matrix4::identity(),
+            Matrix4::translation(1.0, -2.0, 3.0) * Matrix4::rotation_y(75.0),
+            Matrix4::rotation_z(20.0) * Matrix4::scaling(2.0, 0.5, 1.0) * Matrix4::rotation_x(-50.0),
+            Matrix4::translation(0.0, 4.0, 0.0) * Matrix4::shearing(0.5, 0.0, 0.0, 0.2, 0.0, 0.0),
+            Matrix4::rotation_x(30.0) * Matrix4::scaling(-1.0, 1.0, 1.0),
+        ];
+        for m in transformations.iter() {
+            assert_eq!(Decomposition::new(m).to_matrix(), *m);
+        }
+
+        let d = Decomposition::new(&(Matrix4::translation(1.0, 2.0, 3.0) * Matrix4::rotation_y(75.0)));
+        assert_eq!(d.translation, Tuple::vector(1.0, 2.0, 3.0));
+        assert_eq!(d.rotation.to_matrix(), Matrix4::rotation_y(75.0));
+        assert_eq!(d.stretch, Matrix4::identity());
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let a = Decomposition::new(&Matrix4::translation(0.0, 0.0, 0.0));
+        let b = Decomposition::new(&(Matrix4::translation(4.0, 0.0, 0.0) * Matrix4::rotation_z(90.0)));
+        let half = a.interpolate(&b, 0.5).to_matrix();
+        assert_eq!(half, Matrix4::translation(2.0, 0.0, 0.0) * Matrix4::rotation_z(45.0));
+
+        // A point on a rotating object keeps its distance from the
+        // centre, unlike with blended matrices.
+        let b = Decomposition::new(&Matrix4::rotation_y(180.0 - 1e-6));
+        let p = a.interpolate(&b, 0.5).to_matrix() * Tuple::point(1.0, 0.0, 0.0);
+        assert!((p - Tuple::point(0.0, 0.0, 0.0)).magnitude() > 0.999);
+
+        let a = Decomposition::new(&Matrix4::scaling(1.0, 1.0, 1.0));
+        let b = Decomposition::new(&Matrix4::scaling(3.0, 1.0, 1.0));
+        assert_eq!(a.interpolate(&b, 0.5).to_matrix(), Matrix4::scaling(2.0, 1.0, 1.0));
+    }
+}
@@ -0,0 +1,313 @@
+use std::array;
+use std::ops::{Index, IndexMut, Mul};
+
+use crate::utils::{equal};
+use crate::linalg::tuple::Tuple;
+
+
+// 4x4 matrix stored on the stack, the type of every transformation. It
+// is Copy and its inverse is computed in closed form, so transforming
+// rays in the hot path never touches the heap.
+#[derive(Debug, Copy, Clone)]
+pub struct Matrix4 {
+    pub data: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+    pub fn new(data: [[f64; 4]; 4]) -> Matrix4 {
+        Matrix4 {
+            data: data,
+        }
+    }
+
+    pub fn identity() -> Matrix4 {
+        Matrix4::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn transpose(&self) -> Matrix4 {
+        Matrix4::new(array::from_fn(|i| array::from_fn(|j| self.data[j][i])))
+    }
+
+    // The 2x2 minors of the upper and lower two rows, shared by the
+    // determinant and the inverse (Laplace expansion along the rows).
+    fn minors(&self) -> ([f64; 6], [f64; 6]) {
+        let m = &self.data;
+        let s = [
+            m[0][0] * m[1][1] - m[1][0] * m[0][1],
+            m[0][0] * m[1][2] - m[1][0] * m[0][2],
+            m[0][0] * m[1][3] - m[1][0] * m[0][3],
+            m[0][1] * m[1][2] - m[1][1] * m[0][2],
+            m[0][1] * m[1][3] - m[1][1] * m[0][3],
+            m[0][2] * m[1][3] - m[1][2] * m[0][3],
+        ];
+        let c = [
+            m[2][0] * m[3][1] - m[3][0] * m[2][1],
+            m[2][0] * m[3][2] - m[3][0] * m[2][2],
+            m[2][0] * m[3][3] - m[3][0] * m[2][3],
+            m[2][1] * m[3][2] - m[3][1] * m[2][2],
+            m[2][1] * m[3][3] - m[3][1] * m[2][3],
+            m[2][2] * m[3][3] - m[3][2] * m[2][3],
+        ];
+        (s, c)
+    }
+
+    pub fn determinant(&self) -> f64 {
+        let (s, c) = self.minors();
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+    }
+
+    pub fn is_invertible(&self) -> bool {
+        !equal(self.determinant(), 0.0)
+    }
+
+    pub fn invert(&self) -> Matrix4 {
+        let (s, c) = self.minors();
+        let det = s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0];
+        if equal(det, 0.0) {
+            panic!("Calling invert() on a non-invertible matrix!");
+        }
+
+        let m = &self.data;
+        let inv_det = 1.0 / det;
+        Matrix4::new([
+            [
+                (m[1][1] * c[5] - m[1][2] * c[4] + m[1][3] * c[3]) * inv_det,
+                (-m[0][1] * c[5] + m[0][2] * c[4] - m[0][3] * c[3]) * inv_det,
+                (m[3][1] * s[5] - m[3][2] * s[4] + m[3][3] * s[3]) * inv_det,
+                (-m[2][1] * s[5] + m[2][2] * s[4] - m[2][3] * s[3]) * inv_det,
+            ],
+            [
+                (-m[1][0] * c[5] + m[1][2] * c[2] - m[1][3] * c[1]) * inv_det,
+                (m[0][0] * c[5] - m[0][2] * c[2] + m[0][3] * c[1]) * inv_det,
+                (-m[3][0] * s[5] + m[3][2] * s[2] - m[3][3] * s[1]) * inv_det,
+                (m[2][0] * s[5] - m[2][2] * s[2] + m[2][3] * s[1]) * inv_det,
+            ],
+            [
+                (m[1][0] * c[4] - m[1][1] * c[2] + m[1][3] * c[0]) * inv_det,
+                (-m[0][0] * c[4] + m[0][1] * c[2] - m[0][3] * c[0]) * inv_det,
+                (m[3][0] * s[4] - m[3][1] * s[2] + m[3][3] * s[0]) * inv_det,
+                (-m[2][0] * s[4] + m[2][1] * s[2] - m[2][3] * s[0]) * inv_det,
+            ],
+            [
+                (-m[1][0] * c[3] + m[1][1] * c[1] - m[1][2] * c[0]) * inv_det,
+                (m[0][0] * c[3] - m[0][1] * c[1] + m[0][2] * c[0]) * inv_det,
+                (-m[3][0] * s[3] + m[3][1] * s[1] - m[3][2] * s[0]) * inv_det,
+                (m[2][0] * s[3] - m[2][1] * s[1] + m[2][2] * s[0]) * inv_det,
+            ],
+        ])
+    }
+}
+
+
+impl PartialEq for Matrix4 {
+    fn eq(&self, other: &Self) -> bool {
+        for i in 0..4 {
+            for j in 0..4 {
+                if !equal(self.data[i][j], other.data[i][j]) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+impl Eq for Matrix4 {}
+
+
+impl Mul for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, other: Matrix4) -> Matrix4 {
+        Matrix4::new(array::from_fn(|i| array::from_fn(|j| {
+            self.data[i][0] * other.data[0][j] +
+            self.data[i][1] * other.data[1][j] +
+            self.data[i][2] * other.data[2][j] +
+            self.data[i][3] * other.data[3][j]
+        })))
+    }
+}
+
+impl Mul<Tuple> for Matrix4 {
+    type Output = Tuple;
+
+    fn mul(self, other: Tuple) -> Tuple {
+        let m = &self.data;
+        Tuple::new(
+            m[0][0] * other.x + m[0][1] * other.y + m[0][2] * other.z + m[0][3] * other.w,
+            m[1][0] * other.x + m[1][1] * other.y + m[1][2] * other.z + m[1][3] * other.w,
+            m[2][0] * other.x + m[2][1] * other.y + m[2][2] * other.z + m[2][3] * other.w,
+            m[3][0] * other.x + m[3][1] * other.y + m[3][2] * other.z + m[3][3] * other.w,
+        )
+    }
+}
+
+impl Index<usize> for Matrix4 {
+    type Output = [f64; 4];
+
+    fn index(&self, row: usize) -> &[f64; 4] {
+        &self.data[row]
+    }
+}
+
+impl IndexMut<usize> for Matrix4 {
+    fn index_mut(&mut self, row: usize) -> &mut [f64; 4] {
+        &mut self.data[row]
+    }
+}
+
+
+impl Matrix4 {
+    pub fn translation(x: f64, y: f64, z: f64) -> Matrix4 {
+        let mut m = Matrix4::identity();
+        m.data[0][3] = x;
+        m.data[1][3] = y;
+        m.data[2][3] = z;
+        m
+    }
+
+    pub fn scaling(x: f64, y: f64, z: f64) -> Matrix4 {
+        let mut m = Matrix4::identity();
+        m.data[0][0] = x;
+        m.data[1][1] = y;
+        m.data[2][2] = z;
+        m
+    }
+
+    pub fn rotation_x(deg: f64) -> Matrix4 {
+        let rad = (deg * std::f64::consts::PI) / 180.0;
+        let mut m = Matrix4::identity();
+        m.data[1][1] = rad.cos();
+        m.data[1][2] = -rad.sin();
+        m.data[2][1] = rad.sin();
+        m.data[2][2] = rad.cos();
+        m
+    }
+
+    pub fn rotation_y(deg: f64) -> Matrix4 {
+        let rad = (deg * std::f64::consts::PI) / 180.0;
+        let mut m = Matrix4::identity();
+        m.data[0][0] = rad.cos();
+        m.data[0][2] = rad.sin();
+        m.data[2][0] = -rad.sin();
+        m.data[2][2] = rad.cos();
+        m
+    }
+
+    pub fn rotation_z(deg: f64) -> Matrix4 {
+        let rad = (deg * std::f64::consts::PI) / 180.0;
+        let mut m = Matrix4::identity();
+        m.data[0][0] = rad.cos();
+        m.data[0][1] = -rad.sin();
+        m.data[1][0] = rad.sin();
+        m.data[1][1] = rad.cos();
+        m
+    }
+
+    pub fn shearing(xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Matrix4 {
+        let mut m = Matrix4::identity();
+        m.data[0][1] = xy;
+        m.data[0][2] = xz;
+        m.data[1][0] = yx;
+        m.data[1][2] = yz;
+        m.data[2][0] = zx;
+        m.data[2][1] = zy;
+        m
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linalg::matrix::Matrix;
+
+    fn random() -> (Matrix4, Matrix) {
+        let m = Matrix::random(4, 4, -10.0, 10.0);
+        let data = array::from_fn(|i| array::from_fn(|j| m.data[i][j]));
+        (Matrix4::new(data), m)
+    }
+
+    fn assert_same(a: &Matrix4, b: &Matrix) {
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!(equal(a.data[i][j], b.data[i][j]), "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_general_matrix() {
+        for _ in 0..100 {
+            let (a4, a) = random();
+            let (b4, b) = random();
+
+            assert!(equal(a4.determinant(), a.determinant()));
+            assert_same(&a4.transpose(), &a.transpose());
+            assert_same(&(a4 * b4), &a.multiply_matrix(&b));
+
+            let t = Tuple::new(1.0, -2.0, 3.0, 1.0);
+            assert_eq!(a4 * t, a.multiply_tuple(&t));
+
+            if a.is_invertible() {
+                assert_same(&a4.invert(), &a.invert());
+            }
+        }
+
+        assert_same(&Matrix4::translation(1.0, -2.0, 3.0), &Matrix::translation(1.0, -2.0, 3.0));
+        assert_same(&Matrix4::scaling(1.0, -2.0, 3.0), &Matrix::scaling(1.0, -2.0, 3.0));
+        assert_same(&Matrix4::rotation_x(30.0), &Matrix::rotation_x(30.0));
+        assert_same(&Matrix4::rotation_y(30.0), &Matrix::rotation_y(30.0));
+        assert_same(&Matrix4::rotation_z(30.0), &Matrix::rotation_z(30.0));
+        assert_same(
+            &Matrix4::shearing(1.0, 2.0, 3.0, 4.0, 5.0, 6.0),
+            &Matrix::shearing(1.0, 2.0, 3.0, 4.0, 5.0, 6.0),
+        );
+    }
+
+    #[test]
+    fn test_determinant() {
+        let m = Matrix4::new([
+            [-2.0, -8.0, 3.0, 5.0],
+            [-3.0, 1.0, 7.0, 3.0],
+            [1.0, 2.0, -9.0, 6.0],
+            [-6.0, 7.0, 7.0, -9.0],
+        ]);
+        assert_eq!(m.determinant(), -4071.0);
+        assert_eq!(m[2][3], 6.0);
+    }
+
+    #[test]
+    fn test_invert() {
+        let m = Matrix4::new([
+            [8.0, -5.0, 9.0, 2.0],
+            [7.0, 5.0, 6.0, 1.0],
+            [-6.0, 0.0, 9.0, 6.0],
+            [-3.0, 0.0, -9.0, -4.0],
+        ]);
+        let expected = Matrix4::new([
+            [-0.15385, -0.15385, -0.28205, -0.53846],
+            [-0.07692, 0.12308, 0.02564, 0.03077],
+            [0.35897, 0.35897, 0.43590, 0.92308],
+            [-0.69231, -0.69231, -0.76923, -1.92308],
+        ]);
+        assert_eq!(m.invert(), expected);
+        assert_eq!(m * m.invert(), Matrix4::identity());
+
+        let t = Matrix4::translation(1.0, 2.0, 3.0) * Matrix4::rotation_y(30.0) * Matrix4::scaling(2.0, 3.0, 4.0);
+        assert_eq!(t.invert() * t, Matrix4::identity());
+    }
+
+    #[test]
+    fn test_transformations() {
+        let p = Tuple::point(1.0, 2.0, 3.0);
+        assert_eq!(Matrix4::translation(1.0, 1.0, 1.0) * p, Tuple::point(2.0, 3.0, 4.0));
+        assert_eq!(Matrix4::scaling(2.0, 2.0, 2.0) * p, Tuple::point(2.0, 4.0, 6.0));
+        assert_eq!(Matrix4::rotation_z(90.0) * p, Tuple::point(-2.0, 1.0, 3.0));
+        assert_eq!(Matrix4::shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0) * p, Tuple::point(3.0, 2.0, 3.0));
+    }
+}
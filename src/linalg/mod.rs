@@ -1,2 +1,10 @@
+pub mod decomposition;
+// General matrices of any size, transformations use the specialised
+// Matrix4. Only kept as the reference implementation it is tested
+// against.
+#[cfg(test)]
 pub mod matrix;
+pub mod matrix4;
+pub mod quaternion;
+pub mod roots;
 pub mod tuple;
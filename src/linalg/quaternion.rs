@@ -0,0 +1,128 @@
+use crate::linalg::matrix4::Matrix4;
+
+
+// Unit quaternion describing a rotation, used to interpolate rotations
+// along the shortest arc instead of blending matrices.
+#[derive(Debug, Copy, Clone)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Quaternion {
+        Quaternion {
+            w: w,
+            x: x,
+            y: y,
+            z: z,
+        }
+    }
+
+    // Rotation of the upper 3x3 part of the matrix, which has to be
+    // orthonormal with a positive determinant.
+    pub fn from_rotation(m: &Matrix4) -> Quaternion {
+        let m = &m.data;
+        let trace = m[0][0] + m[1][1] + m[2][2];
+
+        // Divide by the largest of the four components for accuracy.
+        let result = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quaternion::new(0.25 * s, (m[2][1] - m[1][2]) / s, (m[0][2] - m[2][0]) / s, (m[1][0] - m[0][1]) / s)
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.0;
+            Quaternion::new((m[2][1] - m[1][2]) / s, 0.25 * s, (m[0][1] + m[1][0]) / s, (m[0][2] + m[2][0]) / s)
+        } else if m[1][1] > m[2][2] {
+            let s = (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.0;
+            Quaternion::new((m[0][2] - m[2][0]) / s, (m[0][1] + m[1][0]) / s, 0.25 * s, (m[1][2] + m[2][1]) / s)
+        } else {
+            let s = (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.0;
+            Quaternion::new((m[1][0] - m[0][1]) / s, (m[0][2] + m[2][0]) / s, (m[1][2] + m[2][1]) / s, 0.25 * s)
+        };
+        result.normalize()
+    }
+
+    pub fn to_matrix(self) -> Matrix4 {
+        let Quaternion { w, x, y, z } = self;
+        Matrix4::new([
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z), 2.0 * (x * z + w * y), 0.0],
+            [2.0 * (x * y + w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x), 0.0],
+            [2.0 * (x * z - w * y), 2.0 * (y * z + w * x), 1.0 - 2.0 * (x * x + y * y), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn dot(&self, other: &Quaternion) -> f64 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn normalize(&self) -> Quaternion {
+        let length = self.dot(self).sqrt();
+        Quaternion::new(self.w / length, self.x / length, self.y / length, self.z / length)
+    }
+
+    // Spherical linear interpolation, at a constant angular velocity
+    // along the shorter of the two arcs.
+    pub fn slerp(&self, other: &Quaternion, t: f64) -> Quaternion {
+        let mut cos = self.dot(other);
+        let mut b = *other;
+        if cos < 0.0 {
+            cos = -cos;
+            b = Quaternion::new(-b.w, -b.x, -b.y, -b.z);
+        }
+
+        // Nearly equal rotations, where the sine below vanishes.
+        let (fa, fb) = if cos > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let angle = cos.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+        Quaternion::new(
+            fa * self.w + fb * b.w,
+            fa * self.x + fb * b.x,
+            fa * self.y + fb * b.y,
+            fa * self.z + fb * b.z,
+        ).normalize()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linalg::tuple::Tuple;
+
+    #[test]
+    fn test_from_rotation() {
+        let rotations = [
+            Matrix4::identity(),
+            Matrix4::rotation_x(30.0),
+            Matrix4::rotation_y(170.0),
+            Matrix4::rotation_z(-120.0),
+            Matrix4::rotation_x(180.0),
+            Matrix4::rotation_x(40.0) * Matrix4::rotation_y(-75.0) * Matrix4::rotation_z(160.0),
+        ];
+        for m in rotations.iter() {
+            assert_eq!(Quaternion::from_rotation(m).to_matrix(), *m);
+        }
+    }
+
+    #[test]
+    fn test_slerp() {
+        let a = Quaternion::from_rotation(&Matrix4::rotation_y(10.0));
+        let b = Quaternion::from_rotation(&Matrix4::rotation_y(130.0));
+        assert_eq!(a.slerp(&b, 0.0).to_matrix(), Matrix4::rotation_y(10.0));
+        assert_eq!(a.slerp(&b, 0.25).to_matrix(), Matrix4::rotation_y(40.0));
+        assert_eq!(a.slerp(&b, 1.0).to_matrix(), Matrix4::rotation_y(130.0));
+
+        // The shorter way round is through 180 degrees.
+        let b = Quaternion::from_rotation(&Matrix4::rotation_z(-100.0));
+        let c = Quaternion::from_rotation(&Matrix4::rotation_z(100.0));
+        let p = b.slerp(&c, 0.5).to_matrix() * Tuple::point(1.0, 0.0, 0.0);
+        assert_eq!(p, Tuple::point(-1.0, 0.0, 0.0));
+    }
+}
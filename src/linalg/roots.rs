@@ -0,0 +1,167 @@
+// Closed form solvers for polynomials up to degree four, following
+// Schwarze's "Cubic and Quartic Roots" (Graphics Gems I). Coefficients
+// are given from the highest power down, only real roots are returned
+// in ascending order.
+
+static ROOT_EPSILON: f64 = 1e-9;
+static NEWTON_STEPS: usize = 4;
+
+
+fn is_zero(x: f64) -> bool {
+    x.abs() < ROOT_EPSILON
+}
+
+fn sorted(mut roots: Vec<f64>) -> Vec<f64> {
+    roots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    roots
+}
+
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    let p = b / (2.0 * a);
+    let q = c / a;
+    let d = p * p - q;
+
+    if is_zero(d) {
+        vec![-p]
+    } else if d < 0.0 {
+        vec![]
+    } else {
+        let sqrt_d = d.sqrt();
+        sorted(vec![-sqrt_d - p, sqrt_d - p])
+    }
+}
+
+pub fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    let (a, b, c) = (b / a, c / a, d / a);
+
+    // Substitute x = y - a/3 to eliminate the quadratic term, leaving
+    // y^3 + 3py + 2q = 0.
+    let sq_a = a * a;
+    let p = (-sq_a / 3.0 + b) / 3.0;
+    let q = (2.0 / 27.0 * a * sq_a - a * b / 3.0 + c) / 2.0;
+
+    let cb_p = p * p * p;
+    let discriminant = q * q + cb_p;
+
+    let roots = if is_zero(discriminant) {
+        if is_zero(q) {
+            vec![0.0]
+        } else {
+            let u = (-q).cbrt();
+            vec![2.0 * u, -u]
+        }
+    } else if discriminant < 0.0 {
+        // Three real roots.
+        let phi = (-q / (-cb_p).sqrt()).clamp(-1.0, 1.0).acos() / 3.0;
+        let t = 2.0 * (-p).sqrt();
+        vec![
+            t * phi.cos(),
+            -t * (phi + std::f64::consts::PI / 3.0).cos(),
+            -t * (phi - std::f64::consts::PI / 3.0).cos(),
+        ]
+    } else {
+        let sqrt_d = discriminant.sqrt();
+        vec![(sqrt_d - q).cbrt() - (sqrt_d + q).cbrt()]
+    };
+
+    sorted(roots.into_iter().map(|y| y - a / 3.0).collect())
+}
+
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    let coefficients = [a, b, c, d, e];
+    let (a, b, c, d) = (b / a, c / a, d / a, e / a);
+
+    // Substitute x = y - a/4 to eliminate the cubic term, leaving
+    // y^4 + py^2 + qy + r = 0.
+    let sq_a = a * a;
+    let p = -3.0 / 8.0 * sq_a + b;
+    let q = sq_a * a / 8.0 - a * b / 2.0 + c;
+    let r = -3.0 / 256.0 * sq_a * sq_a + sq_a * b / 16.0 - a * c / 4.0 + d;
+
+    let roots = if is_zero(r) {
+        let mut tmp = solve_cubic(1.0, 0.0, p, q);
+        tmp.push(0.0);
+        tmp
+    } else {
+        // Split into two quadratics using the largest root of the
+        // resolvent cubic.
+        let resolvent = solve_cubic(1.0, -p / 2.0, -r, r * p / 2.0 - q * q / 8.0);
+        let z = resolvent[resolvent.len() - 1];
+
+        let u = z * z - r;
+        let v = 2.0 * z - p;
+        if (u < 0.0 && !is_zero(u)) || (v < 0.0 && !is_zero(v)) {
+            return vec![];
+        }
+        let u = u.max(0.0).sqrt();
+        let v = if q < 0.0 { -v.max(0.0).sqrt() } else { v.max(0.0).sqrt() };
+
+        let mut tmp = solve_quadratic(1.0, v, z - u);
+        tmp.append(&mut solve_quadratic(1.0, -v, z + u));
+        tmp
+    };
+
+    // The closed form loses precision for ill-conditioned inputs, a
+    // few Newton steps on the original polynomial recover it.
+    sorted(roots.into_iter().map(|y| polish(&coefficients, y - a / 4.0)).collect())
+}
+
+fn polish(coefficients: &[f64], mut x: f64) -> f64 {
+    for _ in 0..NEWTON_STEPS {
+        let mut value = 0.0;
+        let mut derivative = 0.0;
+        for &c in coefficients.iter() {
+            derivative = derivative * x + value;
+            value = value * x + c;
+        }
+        if derivative.abs() < ROOT_EPSILON {
+            break;
+        }
+        x -= value / derivative;
+    }
+    x
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::equal;
+
+    fn assert_roots(actual: Vec<f64>, expected: Vec<f64>) {
+        assert_eq!(actual.len(), expected.len(), "{:?} != {:?}", actual, expected);
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert!(equal(*a, *e), "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_quadratic() {
+        assert_roots(solve_quadratic(1.0, -3.0, 2.0), vec![1.0, 2.0]);
+        assert_roots(solve_quadratic(1.0, 2.0, 1.0), vec![-1.0]);
+        assert_roots(solve_quadratic(1.0, 0.0, 1.0), vec![]);
+    }
+
+    #[test]
+    fn test_cubic() {
+        // (x - 1)(x - 2)(x - 3)
+        assert_roots(solve_cubic(1.0, -6.0, 11.0, -6.0), vec![1.0, 2.0, 3.0]);
+        // (x - 2)(x^2 + 1)
+        assert_roots(solve_cubic(2.0, -4.0, 2.0, -4.0), vec![2.0]);
+    }
+
+    #[test]
+    fn test_quartic() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        assert_roots(solve_quartic(1.0, -10.0, 35.0, -50.0, 24.0), vec![1.0, 2.0, 3.0, 4.0]);
+        // (x^2 - 4)(x^2 + 1)
+        assert_roots(solve_quartic(1.0, 0.0, -3.0, 0.0, -4.0), vec![-2.0, 2.0]);
+        // x^4 + 1
+        assert_roots(solve_quartic(1.0, 0.0, 0.0, 0.0, 1.0), vec![]);
+        // (x + 0.5)(x - 0.25)(x - 10)(x - 100)
+        assert_roots(
+            solve_quartic(1.0, -109.75, 972.375, 263.75, -125.0),
+            vec![-0.5, 0.25, 10.0, 100.0]
+        );
+    }
+}
@@ -1,5 +1,7 @@
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign};
+
 use crate::utils::{equal};
-use crate::linalg::matrix::Matrix;
+use crate::linalg::matrix4::Matrix4;
 
 
 #[derive(Debug, Copy, Clone)]
@@ -31,42 +33,12 @@ impl Tuple {
         Tuple::new(xyz[0], xyz[1], xyz[2], 0.0)
     }
 
-    pub fn add(&self, other: &Tuple) -> Tuple {
-        Tuple {
-            x: self.x + other.x,
-            y: self.y + other.y,
-            z: self.z + other.z,
-            w: self.w + other.w,
-        }
-    }
-
-    pub fn subtract(&self, other: &Tuple) -> Tuple {
-        Tuple {
-            x: self.x - other.x,
-            y: self.y - other.y,
-            z: self.z - other.z,
-            w: self.w - other.w,
-        }
-    }
-
-    pub fn negate(&self) -> Tuple {
-        Tuple { x: -self.x, y: -self.y, z: -self.z, w: -self.w, }
-    }
-
-    pub fn multiply(&self, n: f64) -> Tuple {
-        Tuple { x: self.x * n, y: self.y * n, z: self.z * n, w: self.w * n, }
-    }
-
-    pub fn divide(&self, n: f64) -> Tuple {
-        Tuple { x: self.x / n, y: self.y / n, z: self.z / n, w: self.w / n, }
-    }
-
     pub fn magnitude(&self) -> f64 {
         (self.x * self.x + self.y * self.y + self.z * self.z + self.w * self.w).sqrt()
     }
 
     pub fn normalize(&self) -> Tuple {
-        self.divide(self.magnitude())
+        *self / self.magnitude()
     }
 
     pub fn dot(&self, other: &Tuple) -> f64 {
@@ -82,30 +54,30 @@ impl Tuple {
     }
 
     pub fn translate(&self, x: f64, y: f64, z: f64) -> Tuple {
-        Matrix::translation(x, y, z).multiply_tuple(self)
+        Matrix4::translation(x, y, z) * *self
     }
 
     pub fn scale(&self, x: f64, y: f64, z: f64) -> Tuple {
-        Matrix::scaling(x, y, z).multiply_tuple(self)
+        Matrix4::scaling(x, y, z) * *self
     }
 
     pub fn rotate_x(&self, deg: f64) -> Tuple {
         let rad = (deg * std::f64::consts::PI) / 180.0;
-        Matrix::rotation_x(rad).multiply_tuple(self)
+        Matrix4::rotation_x(rad) * *self
     }
 
     pub fn rotate_y(&self, deg: f64) -> Tuple {
         let rad = (deg * std::f64::consts::PI) / 180.0;
-        Matrix::rotation_y(rad).multiply_tuple(self)
+        Matrix4::rotation_y(rad) * *self
     }
 
     pub fn rotate_z(&self, deg: f64) -> Tuple {
         let rad = (deg * std::f64::consts::PI) / 180.0;
-        Matrix::rotation_z(rad).multiply_tuple(self)
+        Matrix4::rotation_z(rad) * *self
     }
 
     pub fn shear(&self, xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Tuple {
-        Matrix::shearing(xy, xz, yx, yz, zx, zy).multiply_tuple(self)
+        Matrix4::shearing(xy, xz, yx, yz, zx, zy) * *self
     }
 
 }
@@ -122,6 +94,120 @@ impl PartialEq for Tuple {
 impl Eq for Tuple {}
 
 
+// Arithmetic works on all four components, so points and vectors keep
+// their w. Debug builds catch the combinations that make no sense
+// geometrically.
+impl Add for Tuple {
+    type Output = Tuple;
+
+    fn add(self, other: Tuple) -> Tuple {
+        debug_assert!(!(self.w == 1.0 && other.w == 1.0), "Adding two points!");
+        Tuple {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+            w: self.w + other.w,
+        }
+    }
+}
+
+impl Sub for Tuple {
+    type Output = Tuple;
+
+    fn sub(self, other: Tuple) -> Tuple {
+        debug_assert!(!(self.w == 0.0 && other.w == 1.0), "Subtracting a point from a vector!");
+        Tuple {
+            x: self.x - other.x,
+            y: self.y - other.y,
+            z: self.z - other.z,
+            w: self.w - other.w,
+        }
+    }
+}
+
+impl Neg for Tuple {
+    type Output = Tuple;
+
+    fn neg(self) -> Tuple {
+        Tuple { x: -self.x, y: -self.y, z: -self.z, w: -self.w, }
+    }
+}
+
+impl Mul<f64> for Tuple {
+    type Output = Tuple;
+
+    fn mul(self, n: f64) -> Tuple {
+        Tuple { x: self.x * n, y: self.y * n, z: self.z * n, w: self.w * n, }
+    }
+}
+
+impl Mul<Tuple> for f64 {
+    type Output = Tuple;
+
+    fn mul(self, t: Tuple) -> Tuple {
+        t * self
+    }
+}
+
+impl Div<f64> for Tuple {
+    type Output = Tuple;
+
+    fn div(self, n: f64) -> Tuple {
+        Tuple { x: self.x / n, y: self.y / n, z: self.z / n, w: self.w / n, }
+    }
+}
+
+impl AddAssign for Tuple {
+    fn add_assign(&mut self, other: Tuple) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Tuple {
+    fn sub_assign(&mut self, other: Tuple) {
+        *self = *self - other;
+    }
+}
+
+impl MulAssign<f64> for Tuple {
+    fn mul_assign(&mut self, n: f64) {
+        *self = *self * n;
+    }
+}
+
+impl DivAssign<f64> for Tuple {
+    fn div_assign(&mut self, n: f64) {
+        *self = *self / n;
+    }
+}
+
+impl Index<usize> for Tuple {
+    type Output = f64;
+
+    fn index(&self, i: usize) -> &f64 {
+        match i {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            3 => &self.w,
+            _ => panic!("Tuple index out of range: {:?}!", i),
+        }
+    }
+}
+
+impl IndexMut<usize> for Tuple {
+    fn index_mut(&mut self, i: usize) -> &mut f64 {
+        match i {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            3 => &mut self.w,
+            _ => panic!("Tuple index out of range: {:?}!", i),
+        }
+    }
+}
+
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -134,7 +220,35 @@ mod tests {
         for _ in 1..10 {
             let a: Tuple = Tuple::new(rng.gen::<f64>() * n, rng.gen::<f64>() * n, rng.gen::<f64>() * n, rng.gen::<f64>() * n);
             let b: Tuple = Tuple::new(rng.gen::<f64>() * n, rng.gen::<f64>() * n, rng.gen::<f64>() * n, rng.gen::<f64>() * n);
-            assert_eq!(a, a.add(&b).subtract(&b));
+            assert_eq!(a, (a + b) - b);
         }
     }
+
+    #[test]
+    fn test_operators() {
+        let p = Tuple::point(3.0, -2.0, 5.0);
+        let v = Tuple::vector(-2.0, 3.0, 1.0);
+        assert_eq!(p + v, Tuple::point(1.0, 1.0, 6.0));
+        assert_eq!(p - v, Tuple::point(5.0, -5.0, 4.0));
+        assert_eq!(p - p, Tuple::vector(0.0, 0.0, 0.0));
+        assert_eq!(-v, Tuple::vector(2.0, -3.0, -1.0));
+        assert_eq!(v * 2.0, Tuple::vector(-4.0, 6.0, 2.0));
+        assert_eq!(0.5 * v, v / 2.0);
+
+        let mut t = v;
+        t += v;
+        t -= v * 3.0;
+        t *= -1.0;
+        assert_eq!(t, Tuple::vector(-2.0, 3.0, 1.0));
+        assert_eq!((t[0], t[1], t[2], t[3]), (-2.0, 3.0, 1.0, 0.0));
+        t[3] = 1.0;
+        assert_eq!(t, Tuple::point(-2.0, 3.0, 1.0));
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn test_add_points() {
+        let _ = Tuple::point(1.0, 0.0, 0.0) + Tuple::point(0.0, 1.0, 0.0);
+    }
 }
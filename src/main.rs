@@ -1,24 +1,33 @@
 use std::path::Path;
-use std::fs::File;
-use std::io::BufWriter;
 use std::time::{SystemTime};
 
-use png;
-
+mod animation;
+mod bvh;
 mod camera;
 mod color;
 mod patterns;
 mod light;
 mod linalg;
 mod material;
+mod obj;
+mod output;
+mod progress;
 mod ray;
+mod sampling;
 mod scene;
+mod sdf;
 mod shapes;
+mod tiles;
+mod tonemap;
 mod utils;
 mod world;
 
 use clap::Parser;
 
+use output::writer::{Dithering, Output};
+use progress::Reporting;
+use tonemap::ToneMapping;
+
 
 #[derive(clap::Parser)]
 #[clap(version = "1.0", author = "Christopher Strecker <chris@foldl.de>")]
@@ -26,42 +35,208 @@ struct Opts {
     #[clap(short, long, default_value = "../scenes/test.json")]
     scene_file: String,
 
+    /// Written as PNG, PFM, Radiance HDR or OpenEXR depending on the
+    /// extension
     #[clap(short, long, default_value = "image.png")]
     out_file: String,
+
+    /// Samples per pixel, overrides the scene camera
+    #[clap(short = 'n', long)]
+    samples: Option<usize>,
+
+    /// clamp, reinhard or aces, overrides the scene camera
+    #[clap(short, long)]
+    tone_mapping: Option<String>,
+
+    /// Exposure in stops, overrides the scene camera
+    #[clap(short, long, allow_hyphen_values = true)]
+    exposure: Option<f64>,
+
+    /// none, ordered or blue-noise, overrides the scene camera
+    #[clap(short, long)]
+    dithering: Option<String>,
+
+    /// Write linear values instead of sRGB
+    #[clap(long)]
+    linear: bool,
+
+    /// Renders the frames FIRST-LAST of the animation, or all of them,
+    /// to numbered files. A run of # in the output file is replaced by
+    /// the frame number, otherwise it is appended to the name
+    #[clap(short, long, allow_hyphen_values = true)]
+    frames: Option<String>,
+
+    /// Number of render threads, one per core by default
+    #[clap(short = 'j', long)]
+    threads: Option<usize>,
+
+    /// Print nothing but errors, no progress while rendering
+    #[clap(short, long)]
+    quiet: bool,
 }
 
 
-fn main() {
-    let render_start = SystemTime::now();
+// Frames to render for the --frames option.
+fn frame_range(frames: &str, scene_file: &String) -> Vec<i64> {
+    let parse = |n: &str| n.trim().parse::<i64>()
+        .unwrap_or_else(|_| panic!("Undefined frame range: {:?}!", frames));
+    let (first, last) = match frames {
+        "all" => {
+            let scene = scene::parse_scene(scene_file, 0.0);
+            let animation = scene.animation.expect("The scene has no animation!");
+            (
+                animation.first_frame.unwrap_or(0),
+                animation.last_frame.expect("The animation has no last frame!"),
+            )
+        },
+        // A leading minus belongs to the first frame, e.g. -10--1.
+        _ => match frames.char_indices().skip(1).find(|&(_, c)| c == '-') {
+            Some((i, _)) => (parse(&frames[..i]), parse(&frames[i + 1..])),
+            None => (parse(frames), parse(frames)),
+        },
+    };
+    if last < first {
+        panic!("Frame range {:?} ends before it starts!", frames);
+    }
+    (first..=last).collect()
+}
 
+// Output file of a frame, e.g. frame-###.png becomes frame-007.png and
+// image.png becomes image_0007.png.
+fn frame_path(out_file: &str, frame: i64) -> String {
+    match out_file.find('#') {
+        Some(start) => {
+            let width = out_file[start..].chars().take_while(|c| *c == '#').count();
+            format!("{}{:0width$}{}", &out_file[..start], frame, &out_file[start + width..], width = width)
+        },
+        None => {
+            let path = Path::new(out_file);
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+            let name = match path.extension().and_then(|s| s.to_str()) {
+                Some(extension) => format!("{}_{:04}.{}", stem, frame, extension),
+                None => format!("{}_{:04}", stem, frame),
+            };
+            path.with_file_name(name).to_string_lossy().into_owned()
+        },
+    }
+}
 
-    // Parse the scene file.
-    let opts: Opts = Opts::parse();
-    let parsed_scene = scene::parse_scene(&opts.scene_file);
 
+fn render_frame(opts: &Opts, parsed_scene: &scene::Scene, out_file: &str) {
     // Render the scene.
-    let camera = scene::make_camera(&parsed_scene);
-    let world = scene::make_world(&parsed_scene);
+    let mut camera = scene::make_camera(parsed_scene);
+    if let Some(n) = opts.samples {
+        camera.samples = n;
+    }
+    if !opts.quiet {
+        camera.progress = Reporting::detect();
+    }
+    let world = scene::make_world(parsed_scene);
     let image = camera.render(&world);
+    if camera.adaptive.is_some() && !opts.quiet {
+        let eyes = if camera.stereo.is_some() { 2 } else { 1 };
+        let pixels = (camera.hsize * camera.vsize * eyes) as f64;
+        println!("Adaptive anti-aliasing spent {:?} extra samples ({:.2} per pixel)",
+                 camera.extra_samples(), camera.extra_samples() as f64 / pixels);
+    }
 
     // Write the output image.
-    let path = Path::new(&opts.out_file);
-    let file = File::create(path).unwrap();
-    let ref mut outbuffer = BufWriter::new(file);
-    let mut encoder = png::Encoder::new(
-        outbuffer,
-        parsed_scene.camera.width as u32,
-        parsed_scene.camera.height as u32
-    );
-    encoder.set_color(png::ColorType::Rgb);
-    encoder.set_depth(png::BitDepth::Eight);
+    let output = Output {
+        tone_mapping: ToneMapping::from_name(
+            opts.tone_mapping.as_ref()
+                .or(parsed_scene.camera.tone_mapping.as_ref())
+                .map_or("clamp", |n| n.as_str())
+        ),
+        exposure: opts.exposure.or(parsed_scene.camera.exposure).unwrap_or(0.0),
+        dithering: Dithering::from_name(
+            opts.dithering.as_ref()
+                .or(parsed_scene.camera.dithering.as_ref())
+                .map_or("none", |n| n.as_str())
+        ),
+        linear: opts.linear,
+    };
+    output.write(Path::new(out_file), &image);
+}
 
-    let mut writer = encoder.write_header().unwrap();
-    writer.write_image_data(&image.concat().concat()).unwrap();
 
+fn main() {
+    let render_start = SystemTime::now();
 
+    let opts: Opts = Opts::parse();
+    if let Some(n) = opts.threads {
+        rayon::ThreadPoolBuilder::new().num_threads(n).build_global()
+            .expect("Could not create the render threads!");
+    }
+    match opts.frames.as_ref() {
+        Some(frames) => {
+            for frame in frame_range(frames, &opts.scene_file) {
+                let frame_start = SystemTime::now();
+                let out_file = frame_path(&opts.out_file, frame);
+                let parsed_scene = scene::parse_scene(&opts.scene_file, frame as f64);
+                render_frame(&opts, &parsed_scene, &out_file);
+                if opts.quiet {
+                    continue;
+                }
+                println!("Rendered frame {:?} in {:?} milliseconds to {:?}",
+                         frame, frame_start.elapsed().unwrap().as_millis(), out_file);
+            }
+        },
+        None => {
+            // A still of an animated scene shows its first frame.
+            let mut parsed_scene = scene::parse_scene(&opts.scene_file, 0.0);
+            let frame = parsed_scene.animation.as_ref().and_then(|a| a.first_frame).unwrap_or(0);
+            if frame != 0 {
+                parsed_scene = scene::parse_scene(&opts.scene_file, frame as f64);
+            }
+            render_frame(&opts, &parsed_scene, &opts.out_file);
+        },
+    }
+
+
+    if opts.quiet {
+        return;
+    }
     let render_duration = render_start.elapsed().unwrap().as_millis();
     println!("Rendered {:?} in {:?} milliseconds to {:?}",
              opts.scene_file, render_duration, opts.out_file);
 
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(frames: &str) -> Vec<i64> {
+        frame_range(frames, &"none.json".to_string())
+    }
+
+    #[test]
+    fn test_frame_range() {
+        assert_eq!(range("3"), vec![3]);
+        assert_eq!(range("1-5"), vec![1, 2, 3, 4, 5]);
+        assert_eq!(range("-10--1"), (-10..=-1).collect::<Vec<i64>>());
+        assert_eq!(range("-2-1"), vec![-2, -1, 0, 1]);
+        assert_eq!(range("-3"), vec![-3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ends before it starts")]
+    fn test_reversed_frame_range() {
+        range("5-1");
+    }
+
+    #[test]
+    #[should_panic(expected = "Undefined frame range")]
+    fn test_invalid_frame_range() {
+        range("1-x");
+    }
+
+    #[test]
+    fn test_frame_path() {
+        assert_eq!(frame_path("frame-###.png", 7), "frame-007.png");
+        assert_eq!(frame_path("out/#.exr", 12), "out/12.exr");
+        assert_eq!(frame_path("image.png", 7), "image_0007.png");
+        assert_eq!(frame_path("out/image", 42), "out/image_0042");
+    }
+}
@@ -0,0 +1,294 @@
+use std::fs;
+use std::path::Path;
+
+use crate::linalg::matrix4::Matrix4;
+use crate::linalg::tuple::Tuple;
+use crate::material::Material;
+use crate::shapes::{group::Group, shape::Shape, smooth_triangle::SmoothTriangle, triangle::Triangle};
+
+
+// Indices into the vertex, texture coordinate and normal lists of an
+// OBJ file, already converted to start at zero.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ObjVertex {
+    pub position: usize,
+    pub texture: Option<usize>,
+    pub normal: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjGroup {
+    #[allow(dead_code)]
+    pub name: String,
+    pub faces: Vec<[ObjVertex; 3]>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjFile {
+    pub vertices: Vec<Tuple>,
+    pub normals: Vec<Tuple>,
+    pub texture_coordinates: Vec<Tuple>,
+    pub default_group: ObjGroup,
+    pub groups: Vec<ObjGroup>,
+    pub ignored_lines: usize,
+}
+
+impl ObjFile {
+    pub fn to_group(&self) -> Group {
+        let mut children = self.make_triangles(&self.default_group);
+        for g in self.groups.iter() {
+            children.push(Box::new(Group::new(Matrix4::identity(), self.make_triangles(g))));
+        }
+        Group::new(Matrix4::identity(), children)
+    }
+
+    fn make_triangles(&self, group: &ObjGroup) -> Vec<Box<dyn Shape + Sync>> {
+        let mut result: Vec<Box<dyn Shape + Sync>> = vec![];
+        for face in group.faces.iter() {
+            let p1 = self.vertices[face[0].position];
+            let p2 = self.vertices[face[1].position];
+            let p3 = self.vertices[face[2].position];
+
+            // Like normals, texture coordinates are only used if all
+            // vertices of the face have them.
+            let texture_coordinates = match (face[0].texture, face[1].texture, face[2].texture) {
+                (Some(t1), Some(t2), Some(t3)) => Some([
+                    self.texture_coordinates[t1],
+                    self.texture_coordinates[t2],
+                    self.texture_coordinates[t3],
+                ]),
+                _ => None,
+            };
+
+            // Faces only get smooth shading if all of their vertices
+            // come with a normal.
+            match (face[0].normal, face[1].normal, face[2].normal) {
+                (Some(n1), Some(n2), Some(n3)) => {
+                    let mut triangle = SmoothTriangle::new(
+                        p1, p2, p3,
+                        [self.normals[n1], self.normals[n2], self.normals[n3]],
+                        Matrix4::identity(),
+                        Material::default(),
+                    );
+                    triangle.triangle.texture_coordinates = texture_coordinates;
+                    result.push(Box::new(triangle));
+                },
+                _ => {
+                    let mut triangle = Triangle::new(
+                        p1, p2, p3,
+                        Matrix4::identity(),
+                        Material::default(),
+                    );
+                    triangle.texture_coordinates = texture_coordinates;
+                    result.push(Box::new(triangle));
+                },
+            }
+        }
+        result
+    }
+}
+
+
+pub fn load_obj(path: &Path) -> Group {
+    let contents = fs::read_to_string(path)
+        .unwrap_or_else(|_| panic!("Could not read the OBJ file {:?}!", path));
+    parse_obj(&contents).to_group()
+}
+
+pub fn parse_obj(contents: &str) -> ObjFile {
+    let mut result = ObjFile {
+        vertices: vec![],
+        normals: vec![],
+        texture_coordinates: vec![],
+        default_group: ObjGroup { name: String::new(), faces: vec![] },
+        groups: vec![],
+        ignored_lines: 0,
+    };
+
+    for line in contents.lines() {
+        let mut fields = line.split_whitespace();
+        let kind = match fields.next() {
+            Some(k) => k,
+            None => continue,
+        };
+        let args: Vec<&str> = fields.collect();
+
+        match kind {
+            "v" => {
+                let v = parse_floats(&args);
+                result.vertices.push(Tuple::point(v[0], v[1], v[2]));
+            },
+            "vn" => {
+                let v = parse_floats(&args);
+                result.normals.push(Tuple::vector(v[0], v[1], v[2]));
+            },
+            "vt" => {
+                let v = parse_floats(&args);
+                let w = if v.len() > 1 { v[1] } else { 0.0 };
+                result.texture_coordinates.push(Tuple::point(v[0], w, 0.0));
+            },
+            "f" => {
+                let vertices: Vec<ObjVertex> = args.iter()
+                    .map(|a| parse_face_vertex(a, &result))
+                    .collect();
+
+                // Triangulate polygons as a fan around the first vertex.
+                let group = match result.groups.last_mut() {
+                    Some(g) => g,
+                    None => &mut result.default_group,
+                };
+                for i in 1..vertices.len().saturating_sub(1) {
+                    group.faces.push([vertices[0], vertices[i], vertices[i + 1]]);
+                }
+            },
+            "g" | "o" => {
+                result.groups.push(ObjGroup {
+                    name: args.join(" "),
+                    faces: vec![],
+                });
+            },
+            _ => result.ignored_lines += 1,
+        }
+    }
+
+    result
+}
+
+fn parse_floats(args: &[&str]) -> Vec<f64> {
+    args.iter()
+        .map(|a| a.parse::<f64>().unwrap_or_else(|_| panic!("Invalid number in OBJ file: {:?}!", a)))
+        .collect()
+}
+
+// Face vertices are written as v, v/vt, v//vn or v/vt/vn. Indices start
+// at one, negative indices count back from the last element read.
+fn parse_face_vertex(arg: &str, obj: &ObjFile) -> ObjVertex {
+    let invalid = || -> ! { panic!("Invalid face in OBJ file: {:?}!", arg) };
+    let parts: Vec<&str> = arg.split('/').collect();
+    let index = |i: usize, len: usize| -> Option<usize> {
+        match parts.get(i) {
+            Some(p) if !p.is_empty() => {
+                let n = p.parse::<i64>().unwrap_or_else(|_| invalid());
+                let len = len as i64;
+                if (1..=len).contains(&n) {
+                    Some((n - 1) as usize)
+                } else if (-len..=-1).contains(&n) {
+                    Some((len + n) as usize)
+                } else {
+                    invalid()
+                }
+            },
+            _ => None,
+        }
+    };
+
+    ObjVertex {
+        position: index(0, obj.vertices.len()).unwrap_or_else(|| invalid()),
+        texture: index(1, obj.texture_coordinates.len()),
+        normal: index(2, obj.normals.len()),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::Ray;
+
+    #[test]
+    fn test_ignored_lines() {
+        let obj = parse_obj("There was a young lady named Bright\nwho traveled much faster than light.\n");
+        assert_eq!(obj.ignored_lines, 2);
+    }
+
+    #[test]
+    fn test_vertices() {
+        let obj = parse_obj("v -1 1 0\nv -1.0000 0.5000 0.0000\nv 1 0 0\nv 1 1 0\n");
+        assert_eq!(obj.vertices.len(), 4);
+        assert_eq!(obj.vertices[1], Tuple::point(-1.0, 0.5, 0.0));
+        assert_eq!(obj.vertices[3], Tuple::point(1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_polygon() {
+        let obj = parse_obj("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nv 0 2 0\n\nf 1 2 3 4 5\n");
+        let faces = &obj.default_group.faces;
+        assert_eq!(faces.len(), 3);
+        let positions: Vec<Vec<usize>> = faces.iter()
+            .map(|f| f.iter().map(|v| v.position).collect())
+            .collect();
+        assert_eq!(positions, vec![vec![0, 1, 2], vec![0, 2, 3], vec![0, 3, 4]]);
+    }
+
+    #[test]
+    fn test_groups() {
+        let obj = parse_obj("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\ng First\nf 1 2 3\ng Second\nf 1 3 4\n");
+        assert_eq!(obj.default_group.faces.len(), 0);
+        assert_eq!(obj.groups.len(), 2);
+        assert_eq!(obj.groups[0].name, "First");
+        assert_eq!(obj.groups[1].faces[0][2].position, 3);
+
+        let g = obj.to_group();
+        assert_eq!(g.children.len(), 2);
+    }
+
+    #[test]
+    fn test_face_formats() {
+        let obj = parse_obj("v 0 1 0\nv -1 0 0\nv 1 0 0\nvt 0.5 1\nvn -1 0 0\nvn 1 0 0\nf 1/1/1 2//2 -1/-1\n");
+        let face = obj.default_group.faces[0];
+        assert_eq!(face[0], ObjVertex { position: 0, texture: Some(0), normal: Some(0) });
+        assert_eq!(face[1], ObjVertex { position: 1, texture: None, normal: Some(1) });
+        assert_eq!(face[2], ObjVertex { position: 2, texture: Some(0), normal: None });
+        assert_eq!(obj.normals[1], Tuple::vector(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_smooth_faces() {
+        let obj = parse_obj("v 0 1 0\nv -1 0 0\nv 1 0 0\nvn -1 0 0\nvn 1 0 0\nvn 0 1 0\nf 1//3 2//1 3//2\nf 1 2 3\n");
+        let g = obj.to_group();
+        assert_eq!(g.children.len(), 2);
+
+        let r = Ray::new(0.0, 0.5, -2.0, 0.0, 0.0, 1.0);
+        let ix = g.children[0].intersect(&r);
+        let n = ix[0].object.normal_at_hit(&Tuple::point(0.0, 0.5, 0.0), &ix[0]);
+        assert_eq!(n, Tuple::vector(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_texture_coordinates() {
+        let obj = parse_obj("v 0 1 0\nv -1 0 0\nv 1 0 0\nvt 0.5 1\nvt 0 0\nvt 1 0\nf 1/1 2/2 3/3\nf 1 2/2 3/3\n");
+        let g = obj.to_group();
+        let uv = g.children[0].uv_coordinates(&Tuple::point(0.0, 0.5, 0.0));
+        assert_eq!(uv, Tuple::point(0.5, 0.5, 0.0));
+        let uv = g.children[0].uv_coordinates(&Tuple::point(-1.0, 0.0, 0.0));
+        assert_eq!(uv, Tuple::point(0.0, 0.0, 0.0));
+
+        // Faces with missing texture coordinates keep the position.
+        let uv = g.children[1].uv_coordinates(&Tuple::point(0.0, 0.5, 0.0));
+        assert_eq!(uv, Tuple::point(0.0, 0.5, 0.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid face in OBJ file")]
+    fn test_zero_index() {
+        parse_obj("v 0 1 0\nv -1 0 0\nv 1 0 0\nf 0 1 2\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid face in OBJ file")]
+    fn test_index_out_of_range() {
+        parse_obj("v 0 1 0\nv -1 0 0\nv 1 0 0\nf 1 2 4\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid face in OBJ file")]
+    fn test_negative_index_out_of_range() {
+        parse_obj("v 0 1 0\nv -1 0 0\nv 1 0 0\nf -4 1 2\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid face in OBJ file")]
+    fn test_missing_position() {
+        parse_obj("v 0 1 0\nv -1 0 0\nv 1 0 0\nvt 0 0\nf /1/1 1 2\n");
+    }
+}
@@ -0,0 +1,113 @@
+use std::io::{Result, Write};
+
+use crate::color::Color;
+
+
+fn write_attribute<W: Write>(out: &mut W, name: &str, kind: &str, value: &[u8]) -> Result<()> {
+    out.write_all(name.as_bytes())?;
+    out.write_all(&[0])?;
+    out.write_all(kind.as_bytes())?;
+    out.write_all(&[0])?;
+    out.write_all(&(value.len() as i32).to_le_bytes())?;
+    out.write_all(value)
+}
+
+fn box2i(width: usize, height: usize) -> Vec<u8> {
+    [0, 0, width as i32 - 1, height as i32 - 1].iter()
+        .flat_map(|v| v.to_le_bytes().to_vec())
+        .collect()
+}
+
+// Single part, uncompressed scanline OpenEXR file with 32-bit float
+// channels, one scanline per block.
+pub fn write<W: Write>(out: &mut W, image: &[Vec<Color>]) -> Result<()> {
+    let width = image[0].len();
+    let height = image.len();
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+
+    // Channels have to be sorted by name. Each is stored as name,
+    // pixel type (2 = float), linear flag, padding and sampling.
+    let mut channels = Vec::new();
+    for name in ["B", "G", "R"].iter() {
+        channels.extend_from_slice(name.as_bytes());
+        channels.push(0);
+        channels.extend_from_slice(&2_i32.to_le_bytes());
+        channels.extend_from_slice(&[0, 0, 0, 0]);
+        channels.extend_from_slice(&1_i32.to_le_bytes());
+        channels.extend_from_slice(&1_i32.to_le_bytes());
+    }
+    channels.push(0);
+    write_attribute(&mut header, "channels", "chlist", &channels)?;
+    write_attribute(&mut header, "compression", "compression", &[0])?;
+    write_attribute(&mut header, "dataWindow", "box2i", &box2i(width, height))?;
+    write_attribute(&mut header, "displayWindow", "box2i", &box2i(width, height))?;
+    write_attribute(&mut header, "lineOrder", "lineOrder", &[0])?;
+    write_attribute(&mut header, "pixelAspectRatio", "float", &1.0_f32.to_le_bytes())?;
+    write_attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8])?;
+    write_attribute(&mut header, "screenWindowWidth", "float", &1.0_f32.to_le_bytes())?;
+    header.push(0);
+    out.write_all(&header)?;
+
+    // Offset table pointing at the start of every block, a block is the
+    // line number, the data size and the channels one after another.
+    let line_size = width * 3 * 4;
+    let block_size = 8 + line_size;
+    let first_block = header.len() + height * 8;
+    for y in 0..height {
+        out.write_all(&((first_block + y * block_size) as u64).to_le_bytes())?;
+    }
+
+    for (y, row) in image.iter().enumerate() {
+        out.write_all(&(y as i32).to_le_bytes())?;
+        out.write_all(&(line_size as i32).to_le_bytes())?;
+        for i in [2, 1, 0].iter() {
+            for c in row.iter() {
+                out.write_all(&(c[*i] as f32).to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_i32(data: &[u8], i: usize) -> i32 {
+        i32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]])
+    }
+
+    fn read_f32(data: &[u8], i: usize) -> f32 {
+        f32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]])
+    }
+
+    #[test]
+    fn test_write() {
+        let image = vec![
+            vec![Color::new(1.0, 2.0, 3.0), Color::new(4.0, 5.0, 6.0), Color::new(7.0, 8.0, 9.0)],
+            vec![Color::new(0.5, 0.25, 0.125), Color::black(), Color::white()],
+        ];
+        let mut out = Vec::new();
+        write(&mut out, &image).unwrap();
+
+        assert_eq!(out[..8], [0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+        let header = out.windows(17).position(|w| w == b"dataWindow\0box2i\0").unwrap();
+        let window: Vec<i32> = (0..4).map(|k| read_i32(&out, header + 21 + k * 4)).collect();
+        assert_eq!(window, vec![0, 0, 2, 1]);
+
+        // Follow the offset table to the second line.
+        let table = out.len() - 2 * (8 + 3 * 3 * 4) - 2 * 8;
+        let offset = u64::from_le_bytes([
+            out[table + 8], out[table + 9], out[table + 10], out[table + 11],
+            out[table + 12], out[table + 13], out[table + 14], out[table + 15],
+        ]) as usize;
+        assert_eq!(read_i32(&out, offset), 1);
+        assert_eq!(read_i32(&out, offset + 4), 36);
+        let values: Vec<f32> = (0..9).map(|k| read_f32(&out, offset + 8 + k * 4)).collect();
+        assert_eq!(values, vec![0.125, 0.0, 1.0, 0.25, 0.0, 1.0, 0.5, 0.0, 1.0]);
+        assert_eq!(offset + 8 + 36, out.len());
+    }
+}
@@ -0,0 +1,5 @@
+pub mod writer;
+
+pub mod exr;
+pub mod pfm;
+pub mod radiance;
@@ -0,0 +1,45 @@
+use std::io::{Result, Write};
+
+use crate::color::Color;
+
+
+// Portable float map: a short text header followed by raw 32-bit
+// floats. The negative scale marks little endian data, rows are stored
+// bottom to top.
+pub fn write<W: Write>(out: &mut W, image: &[Vec<Color>]) -> Result<()> {
+    write!(out, "PF\n{} {}\n-1.0\n", image[0].len(), image.len())?;
+    for row in image.iter().rev() {
+        for c in row.iter() {
+            for i in 0..3 {
+                out.write_all(&(c[i] as f32).to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write() {
+        let image = vec![
+            vec![Color::new(1.0, 2.0, 3.0), Color::new(0.5, 0.0, 0.0)],
+            vec![Color::new(0.0, 0.0, 4.0), Color::new(0.0, 8.0, 0.0)],
+        ];
+        let mut out = Vec::new();
+        write(&mut out, &image).unwrap();
+
+        assert_eq!(&out[..12], b"PF\n2 2\n-1.0\n");
+        assert_eq!(out.len(), 12 + 2 * 2 * 3 * 4);
+
+        // The first pixel written is the bottom left one.
+        let floats: Vec<f32> = out[12..].chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        assert_eq!(floats[..6], [0.0, 0.0, 4.0, 0.0, 8.0, 0.0]);
+        assert_eq!(floats[6..9], [1.0, 2.0, 3.0]);
+    }
+}
@@ -0,0 +1,172 @@
+use std::io::{Result, Write};
+
+use crate::color::Color;
+
+
+// Shared exponent encoding: the mantissas of all three channels use the
+// exponent of the brightest one.
+pub fn to_rgbe(c: &Color) -> [u8; 4] {
+    let v = c.r.max(c.g).max(c.b);
+    if v < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    // v = m * 2^e with m in [0.5, 1).
+    let mut e = v.log2().floor() as i32 + 1;
+    let mut m = v / 2.0_f64.powi(e);
+    if m >= 1.0 {
+        m /= 2.0;
+        e += 1;
+    }
+    let scale = m * 256.0 / v;
+    [
+        (c.r.max(0.0) * scale) as u8,
+        (c.g.max(0.0) * scale) as u8,
+        (c.b.max(0.0) * scale) as u8,
+        (e + 128) as u8,
+    ]
+}
+
+// Run-length encodes one channel of a scanline. Runs of at least four
+// equal bytes are stored as (128 + length, value), everything in
+// between as (length, bytes...), both up to 127 bytes long.
+fn write_channel<W: Write>(out: &mut W, data: &[u8]) -> Result<()> {
+    let mut i = 0;
+    while i < data.len() {
+        // Find the next run long enough to be worth encoding.
+        let mut run_start = i;
+        let mut run_length = 0;
+        while run_start < data.len() {
+            run_length = 1;
+            while run_start + run_length < data.len() && run_length < 127
+                && data[run_start + run_length] == data[run_start] {
+                run_length += 1;
+            }
+            if run_length >= 4 {
+                break;
+            }
+            run_start += run_length;
+        }
+
+        // Literal bytes up to the run.
+        while i < run_start {
+            let n = (run_start - i).min(127);
+            out.write_all(&[n as u8])?;
+            out.write_all(&data[i..i + n])?;
+            i += n;
+        }
+
+        if run_start < data.len() {
+            out.write_all(&[128 + run_length as u8, data[run_start]])?;
+            i = run_start + run_length;
+        }
+    }
+    Ok(())
+}
+
+// Radiance RGBE image with run-length encoded scanlines. Widths outside
+// of what the run-length encoding supports are written flat.
+pub fn write<W: Write>(out: &mut W, image: &[Vec<Color>]) -> Result<()> {
+    let width = image[0].len();
+    write!(out, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", image.len(), width)?;
+
+    for row in image.iter() {
+        let pixels: Vec<[u8; 4]> = row.iter().map(to_rgbe).collect();
+        if !(8..=0x7fff).contains(&width) {
+            for p in pixels.iter() {
+                out.write_all(p)?;
+            }
+            continue;
+        }
+
+        out.write_all(&[2, 2, (width >> 8) as u8, (width & 0xff) as u8])?;
+        for i in 0..4 {
+            let channel: Vec<u8> = pixels.iter().map(|p| p[i]).collect();
+            write_channel(out, &channel)?;
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_rgbe(p: &[u8]) -> Color {
+        if p[3] == 0 {
+            return Color::black();
+        }
+        let f = 2.0_f64.powi(p[3] as i32 - 136);
+        Color::new(p[0] as f64 * f, p[1] as f64 * f, p[2] as f64 * f)
+    }
+
+    // Decodes one run-length encoded channel of the given width.
+    fn read_channel(data: &[u8], width: usize) -> (Vec<u8>, usize) {
+        let mut result = Vec::new();
+        let mut i = 0;
+        while result.len() < width {
+            if data[i] > 128 {
+                let n = (data[i] - 128) as usize;
+                result.extend(std::iter::repeat_n(data[i + 1], n));
+                i += 2;
+            } else {
+                let n = data[i] as usize;
+                result.extend_from_slice(&data[i + 1..i + 1 + n]);
+                i += 1 + n;
+            }
+        }
+        (result, i)
+    }
+
+    #[test]
+    fn test_to_rgbe() {
+        assert_eq!(to_rgbe(&Color::black()), [0, 0, 0, 0]);
+        assert_eq!(to_rgbe(&Color::new(1.0, 0.5, 0.0)), [128, 64, 0, 129]);
+        assert_eq!(to_rgbe(&Color::new(0.0, 0.0, 100.0)), [0, 0, 200, 135]);
+
+        let c = Color::new(3.7, 0.2, 12.25);
+        let d = from_rgbe(&to_rgbe(&c));
+        assert!((d.r - c.r).abs() < 0.07 && (d.g - c.g).abs() < 0.07 && (d.b - c.b).abs() < 0.07);
+    }
+
+    #[test]
+    fn test_write_channel() {
+        let data = [1, 2, 3, 3, 3, 3, 3, 3, 4, 5, 5];
+        let mut out = Vec::new();
+        write_channel(&mut out, &data).unwrap();
+        assert_eq!(out, vec![2, 1, 2, 134, 3, 3, 4, 5, 5]);
+
+        let data: Vec<u8> = (0..300).map(|i| if i < 200 { 7 } else { (i % 13) as u8 }).collect();
+        let mut out = Vec::new();
+        write_channel(&mut out, &data).unwrap();
+        assert_eq!(read_channel(&out, data.len()), (data, out.len()));
+    }
+
+    #[test]
+    fn test_write() {
+        let row: Vec<Color> = (0..20).map(|i| Color::new(i as f64, 0.5, 0.0)).collect();
+        let image = vec![row.clone(), row];
+        let mut out = Vec::new();
+        write(&mut out, &image).unwrap();
+
+        let header = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 20\n";
+        assert_eq!(&out[..header.len()], &header[..]);
+
+        let mut i = header.len();
+        for _ in 0..2 {
+            assert_eq!(out[i..i + 4], [2, 2, 0, 20]);
+            i += 4;
+            let mut channels = Vec::new();
+            for _ in 0..4 {
+                let (channel, n) = read_channel(&out[i..], 20);
+                channels.push(channel);
+                i += n;
+            }
+            for x in 0..20 {
+                let p = [channels[0][x], channels[1][x], channels[2][x], channels[3][x]];
+                assert_eq!(p, to_rgbe(&image[0][x]));
+            }
+        }
+        assert_eq!(i, out.len());
+    }
+}
@@ -0,0 +1,316 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::color::Color;
+use crate::output::{exr, pfm, radiance};
+use crate::tonemap::ToneMapping;
+
+use png;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Dithering {
+    None,
+    // Bayer matrix, cheap but leaves a visible cross-hatch pattern.
+    Ordered,
+    // Void-and-cluster mask, the noise has no low frequencies and is
+    // far less noticeable than ordered or white noise.
+    BlueNoise,
+}
+
+impl Dithering {
+    pub fn from_name(name: &str) -> Dithering {
+        match name {
+            "none" => Dithering::None,
+            "ordered" => Dithering::Ordered,
+            "blue-noise" => Dithering::BlueNoise,
+            _ => panic!("Undefined dithering: {:?}!", name),
+        }
+    }
+
+    // Square threshold map with values in (0, 1), tiled over the image.
+    fn threshold_map(&self) -> (usize, Vec<f64>) {
+        match self {
+            Dithering::None => (1, vec![0.5]),
+            Dithering::Ordered => (8, bayer_matrix(8)),
+            Dithering::BlueNoise => (64, blue_noise(64)),
+        }
+    }
+}
+
+
+// The output stage writing the rendered radiance. PNG images are tone
+// mapped to 8-bit pixels, floating point formats keep the unbounded
+// radiance and only apply the exposure.
+pub struct Output {
+    pub tone_mapping: ToneMapping,
+    pub exposure: f64,
+    pub dithering: Dithering,
+    // Write linear values instead of encoding them as sRGB, for
+    // compositing with tools that expect linear input.
+    pub linear: bool,
+}
+
+impl Output {
+    // The format is chosen by the file extension.
+    pub fn write(&self, path: &Path, image: &[Vec<Color>]) {
+        // None of the formats can store an image without pixels.
+        if image.first().is_none_or(|row| row.is_empty()) {
+            panic!("Cannot write an image without pixels to {:?}!", path);
+        }
+
+        let extension = path.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+        let writer: fn(&mut BufWriter<File>, &[Vec<Color>]) -> std::io::Result<()> = match extension.as_deref() {
+            Some("png") => return self.write_png(path, image),
+            Some("pfm") => pfm::write,
+            Some("hdr") => radiance::write,
+            Some("exr") => exr::write,
+            _ => panic!("Unsupported output format: {:?}!", path),
+        };
+
+        let factor = 2.0_f64.powf(self.exposure);
+        let exposed: Vec<Vec<Color>> = image.iter()
+            .map(|row| row.iter().map(|c| *c * factor).collect())
+            .collect();
+        let mut out = BufWriter::new(File::create(path).unwrap());
+        writer(&mut out, &exposed).unwrap();
+        out.flush().unwrap();
+    }
+
+    pub fn quantize(&self, image: &[Vec<Color>]) -> Vec<u8> {
+        let (size, thresholds) = self.dithering.threshold_map();
+
+        let mut result = Vec::new();
+        for (y, row) in image.iter().enumerate() {
+            for (x, c) in row.iter().enumerate() {
+                let mapped = self.tone_mapping.apply(c, self.exposure);
+                let offset = thresholds[(y % size) * size + x % size] - 0.5;
+                for i in 0..3 {
+                    let v = if self.linear { mapped[i] } else { linear_to_srgb(mapped[i]) };
+                    result.push((v * 255.0 + offset).round().clamp(0.0, 255.0) as u8);
+                }
+            }
+        }
+        result
+    }
+
+    pub fn write_png(&self, path: &Path, image: &[Vec<Color>]) {
+        let file = File::create(path).unwrap();
+        let ref mut outbuffer = BufWriter::new(file);
+        let mut encoder = png::Encoder::new(
+            outbuffer,
+            image[0].len() as u32,
+            image.len() as u32
+        );
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        if self.linear {
+            encoder.set_source_gamma(png::ScaledFloat::new(1.0));
+        } else {
+            encoder.set_srgb(png::SrgbRenderingIntent::Perceptual);
+        }
+
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&self.quantize(image)).unwrap();
+    }
+}
+
+
+// The sRGB transfer function, linear segment near black and a 2.4
+// power curve above.
+pub fn linear_to_srgb(x: f64) -> f64 {
+    if x <= 0.0031308 {
+        12.92 * x
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn to_thresholds(ranks: &[usize]) -> Vec<f64> {
+    let n = ranks.len() as f64;
+    ranks.iter().map(|&r| (r as f64 + 0.5) / n).collect()
+}
+
+// Built recursively, every step replaces each entry r by the 2x2 block
+// [4r, 4r + 2; 4r + 3, 4r + 1].
+fn bayer_matrix(size: usize) -> Vec<f64> {
+    let mut ranks = vec![0];
+    let mut n = 1;
+    while n < size {
+        let mut next = vec![0; 4 * n * n];
+        for y in 0..n {
+            for x in 0..n {
+                let r = 4 * ranks[y * n + x];
+                next[y * 2 * n + x] = r;
+                next[y * 2 * n + x + n] = r + 2;
+                next[(y + n) * 2 * n + x] = r + 3;
+                next[(y + n) * 2 * n + x + n] = r + 1;
+            }
+        }
+        ranks = next;
+        n *= 2;
+    }
+    to_thresholds(&ranks)
+}
+
+// Ulichney's void-and-cluster method. The energy of a pixel is the sum
+// of Gaussians around all set pixels (wrapping around, so the mask
+// tiles), the tightest cluster is the set pixel with the highest energy
+// and the largest void the free pixel with the lowest.
+fn blue_noise(size: usize) -> Vec<f64> {
+    let n = size * size;
+    let sigma: f64 = 1.5;
+
+    let mut weights = vec![0.0; n];
+    for dy in 0..size {
+        for dx in 0..size {
+            let fx = dx.min(size - dx) as f64;
+            let fy = dy.min(size - dy) as f64;
+            weights[dy * size + dx] = (-(fx * fx + fy * fy) / (2.0 * sigma * sigma)).exp();
+        }
+    }
+
+    let set = |ones: &mut Vec<bool>, energy: &mut Vec<f64>, p: usize, value: bool| {
+        ones[p] = value;
+        let sign = if value { 1.0 } else { -1.0 };
+        let (px, py) = (p % size, p / size);
+        for y in 0..size {
+            for x in 0..size {
+                let d = ((y + size - py) % size) * size + (x + size - px) % size;
+                energy[y * size + x] += sign * weights[d];
+            }
+        }
+    };
+    let tightest_cluster = |ones: &Vec<bool>, energy: &Vec<f64>| {
+        (0..n).filter(|&p| ones[p])
+            .max_by(|&a, &b| energy[a].partial_cmp(&energy[b]).unwrap())
+            .unwrap()
+    };
+    let largest_void = |ones: &Vec<bool>, energy: &Vec<f64>| {
+        (0..n).filter(|&p| !ones[p])
+            .min_by(|&a, &b| energy[a].partial_cmp(&energy[b]).unwrap())
+            .unwrap()
+    };
+
+    // Start with a tenth of the pixels set at random and spread them
+    // out by moving the tightest cluster into the largest void until
+    // that no longer changes anything.
+    let mut rng = StdRng::seed_from_u64(0);
+    let initial = n / 10;
+    let mut ones = vec![false; n];
+    let mut energy = vec![0.0; n];
+    let mut count = 0;
+    while count < initial {
+        let p = rng.gen_range(0..n);
+        if !ones[p] {
+            set(&mut ones, &mut energy, p, true);
+            count += 1;
+        }
+    }
+    for _ in 0..n {
+        let cluster = tightest_cluster(&ones, &energy);
+        set(&mut ones, &mut energy, cluster, false);
+        let void = largest_void(&ones, &energy);
+        set(&mut ones, &mut energy, void, true);
+        if void == cluster {
+            break;
+        }
+    }
+
+    // The initial points are ranked by removing the tightest clusters,
+    // all others by filling the largest voids.
+    let mut ranks = vec![0; n];
+    let (mut prototype, mut prototype_energy) = (ones.clone(), energy.clone());
+    for rank in (0..initial).rev() {
+        let cluster = tightest_cluster(&prototype, &prototype_energy);
+        set(&mut prototype, &mut prototype_energy, cluster, false);
+        ranks[cluster] = rank;
+    }
+    for rank in initial..n {
+        let void = largest_void(&ones, &energy);
+        set(&mut ones, &mut energy, void, true);
+        ranks[void] = rank;
+    }
+    to_thresholds(&ranks)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::equal;
+
+    #[test]
+    fn test_linear_to_srgb() {
+        assert_eq!(linear_to_srgb(0.0), 0.0);
+        assert!(equal(linear_to_srgb(1.0), 1.0));
+        assert!(equal(linear_to_srgb(0.5), 0.73536));
+        assert!(equal(linear_to_srgb(0.002), 0.02584));
+    }
+
+    #[test]
+    fn test_quantize() {
+        let image = vec![vec![Color::new(0.5, 1.5, 0.0), Color::new(0.2, 0.0, 1.0)]];
+        let mut output = Output {
+            tone_mapping: ToneMapping::Clamp,
+            exposure: 0.0,
+            dithering: Dithering::None,
+            linear: false,
+        };
+        assert_eq!(output.quantize(&image), vec![188, 255, 0, 124, 0, 255]);
+
+        output.linear = true;
+        assert_eq!(output.quantize(&image), vec![128, 255, 0, 51, 0, 255]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot write an image without pixels")]
+    fn test_write_empty() {
+        let output = Output {
+            tone_mapping: ToneMapping::Clamp,
+            exposure: 0.0,
+            dithering: Dithering::None,
+            linear: false,
+        };
+        output.write(Path::new("empty.png"), &vec![vec![]; 3]);
+    }
+
+    #[test]
+    fn test_dithering() {
+        // A flat gray halfway between two levels comes out as an even
+        // mix of both.
+        let gray = Color::new(100.5 / 255.0, 0.0, 0.0);
+        let image = vec![vec![gray; 64]; 64];
+        for &dithering in [Dithering::Ordered, Dithering::BlueNoise].iter() {
+            let output = Output {
+                tone_mapping: ToneMapping::Clamp,
+                exposure: 0.0,
+                dithering: dithering,
+                linear: true,
+            };
+            let reds: Vec<u8> = output.quantize(&image).iter().step_by(3).cloned().collect();
+            assert!(reds.iter().all(|&r| r == 100 || r == 101));
+            assert_eq!(reds.iter().filter(|&&r| r == 101).count(), 64 * 64 / 2);
+        }
+    }
+
+    #[test]
+    fn test_threshold_maps() {
+        assert_eq!(bayer_matrix(2), vec![0.125, 0.625, 0.875, 0.375]);
+
+        for map in [bayer_matrix(8), blue_noise(16)].iter() {
+            // Every threshold appears exactly once.
+            let mut sorted = map.clone();
+            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let n = sorted.len() as f64;
+            for (i, t) in sorted.iter().enumerate() {
+                assert_eq!(*t, (i as f64 + 0.5) / n);
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::color::Color;
-use crate::linalg::matrix::Matrix;
+use crate::linalg::matrix4::Matrix4;
 use crate::linalg::tuple::Tuple;
 use crate::patterns::pattern::Pattern;
 use crate::shapes::shape::Shape;
@@ -9,13 +9,13 @@ use crate::shapes::shape::Shape;
 pub struct GradientPattern {
     pub a: Color,
     pub b: Color,
-    pub transformation: Matrix,
-    pub inv_transformation: Matrix,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
 }
 
 impl GradientPattern {
     #[allow(dead_code)]
-    pub fn new(a: Color, b: Color, transformation: Matrix) -> GradientPattern {
+    pub fn new(a: Color, b: Color, transformation: Matrix4) -> GradientPattern {
         GradientPattern {
             a: a,
             b: b,
@@ -28,14 +28,13 @@ impl GradientPattern {
 impl Pattern for GradientPattern {
     fn color_at<'a>(&self, object: &'a (dyn Shape + Sync), p: &Tuple) -> Color {
         let pattern_pos = self.convert_position(object, p);
-        self.a.add(&self.b.subtract(&self.a)
-                   .multiply(pattern_pos.x - pattern_pos.x.floor()))
+        self.a + (self.b - self.a) * (pattern_pos.x - pattern_pos.x.floor())
     }
 
-    fn get_transformation(&self) -> &Matrix {
+    fn get_transformation(&self) -> &Matrix4 {
         &self.transformation
     }
-    fn get_inverse_transformation(&self) -> &Matrix {
+    fn get_inverse_transformation(&self) -> &Matrix4 {
         &self.inv_transformation
     }
 
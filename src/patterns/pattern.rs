@@ -1,5 +1,5 @@
 use crate::color::Color;
-use crate::linalg::matrix::Matrix;
+use crate::linalg::matrix4::Matrix4;
 use crate::linalg::tuple::Tuple;
 use crate::shapes::shape::Shape;
 
@@ -7,12 +7,12 @@ use crate::shapes::shape::Shape;
 pub trait Pattern {
     fn color_at<'a>(&self, object: &'a (dyn Shape + Sync), p: &Tuple) -> Color;
 
-    fn get_transformation(&self) -> &Matrix;
-    fn get_inverse_transformation(&self) -> &Matrix;
+    fn get_transformation(&self) -> &Matrix4;
+    fn get_inverse_transformation(&self) -> &Matrix4;
 
     fn convert_position<'a>(&self, object: &'a (dyn Shape + Sync), p: &Tuple) -> Tuple {
-        let object_pos = object.get_inverse_transformation().multiply_tuple(p);
-        self.get_inverse_transformation().multiply_tuple(&object_pos)
+        let object_pos = *object.get_inverse_transformation() * *p;
+        *self.get_inverse_transformation() * object_pos
     }
 
     fn box_clone(&self) -> Box<dyn Pattern + Sync>;
@@ -1,5 +1,5 @@
 use crate::color::Color;
-use crate::linalg::matrix::Matrix;
+use crate::linalg::matrix4::Matrix4;
 use crate::linalg::tuple::Tuple;
 use crate::patterns::pattern::Pattern;
 use crate::shapes::shape::Shape;
@@ -9,12 +9,12 @@ use crate::shapes::shape::Shape;
 pub struct RingPattern {
     pub a: Color,
     pub b: Color,
-    pub transformation: Matrix,
-    pub inv_transformation: Matrix,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
 }
 
 impl RingPattern {
-    pub fn new(a: Color, b: Color, transformation: Matrix) -> RingPattern {
+    pub fn new(a: Color, b: Color, transformation: Matrix4) -> RingPattern {
         RingPattern {
             a: a,
             b: b,
@@ -34,10 +34,10 @@ impl Pattern for RingPattern {
         }
     }
 
-    fn get_transformation(&self) -> &Matrix {
+    fn get_transformation(&self) -> &Matrix4 {
         &self.transformation
     }
-    fn get_inverse_transformation(&self) -> &Matrix {
+    fn get_inverse_transformation(&self) -> &Matrix4 {
         &self.inv_transformation
     }
 
@@ -1,5 +1,5 @@
 use crate::color::Color;
-use crate::linalg::matrix::Matrix;
+use crate::linalg::matrix4::Matrix4;
 use crate::linalg::tuple::Tuple;
 use crate::patterns::pattern::Pattern;
 use crate::shapes::shape::Shape;
@@ -9,12 +9,12 @@ use crate::shapes::shape::Shape;
 pub struct StripePattern {
     pub a: Color,
     pub b: Color,
-    pub transformation: Matrix,
-    pub inv_transformation: Matrix,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
 }
 
 impl StripePattern {
-    pub fn new(a: Color, b: Color, transformation: Matrix) -> StripePattern {
+    pub fn new(a: Color, b: Color, transformation: Matrix4) -> StripePattern {
         StripePattern {
             a: a,
             b: b,
@@ -33,10 +33,10 @@ impl Pattern for StripePattern {
         }
     }
 
-    fn get_transformation(&self) -> &Matrix {
+    fn get_transformation(&self) -> &Matrix4 {
         &self.transformation
     }
-    fn get_inverse_transformation(&self) -> &Matrix {
+    fn get_inverse_transformation(&self) -> &Matrix4 {
         &self.inv_transformation
     }
 
@@ -1,5 +1,5 @@
 use crate::color::Color;
-use crate::linalg::matrix::Matrix;
+use crate::linalg::matrix4::Matrix4;
 use crate::linalg::tuple::Tuple;
 use crate::patterns::pattern::Pattern;
 use crate::shapes::shape::Shape;
@@ -7,13 +7,13 @@ use crate::shapes::shape::Shape;
 
 #[derive(Debug, Clone)]
 pub struct TestPattern {
-    pub transformation: Matrix,
-    pub inv_transformation: Matrix,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
 }
 
 impl TestPattern {
     #[allow(dead_code)]
-    pub fn new(transformation: Matrix) -> TestPattern {
+    pub fn new(transformation: Matrix4) -> TestPattern {
         TestPattern {
             inv_transformation: transformation.invert(),
             transformation: transformation,
@@ -27,10 +27,10 @@ impl Pattern for TestPattern {
         Color::new(px.x, px.y, px.z)
     }
 
-    fn get_transformation(&self) -> &Matrix {
+    fn get_transformation(&self) -> &Matrix4 {
         &self.transformation
     }
-    fn get_inverse_transformation(&self) -> &Matrix {
+    fn get_inverse_transformation(&self) -> &Matrix4 {
         &self.inv_transformation
     }
 
@@ -0,0 +1,166 @@
+use std::io::{self, IsTerminal, Write};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+
+// How often the progress line is redrawn on a terminal, and how often a
+// line is printed otherwise, e.g. into a log file.
+const TERMINAL_INTERVAL: Duration = Duration::from_millis(200);
+const LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Reporting {
+    Quiet,
+    // Redraws a single line.
+    Terminal,
+    // Prints a plain line now and then.
+    Log,
+}
+
+impl Reporting {
+    // Terminal or log lines, depending on where stderr goes.
+    pub fn detect() -> Reporting {
+        if io::stderr().is_terminal() {
+            Reporting::Terminal
+        } else {
+            Reporting::Log
+        }
+    }
+}
+
+
+// Progress of a render on stderr: tiles done, rays traced per second
+// and the time left, assuming the remaining tiles take as long as the
+// ones done so far.
+pub struct Progress {
+    reporting: Reporting,
+    total: usize,
+    done: AtomicUsize,
+    rays: AtomicUsize,
+    start: Instant,
+    last_report: Mutex<Instant>,
+}
+
+impl Progress {
+    pub fn new(reporting: Reporting, total: usize) -> Progress {
+        let now = Instant::now();
+        Progress {
+            reporting: reporting,
+            total: total,
+            done: AtomicUsize::new(0),
+            rays: AtomicUsize::new(0),
+            start: now,
+            last_report: Mutex::new(now),
+        }
+    }
+
+    // Called after every tile with the number of rays traced for it.
+    pub fn tile_done(&self, rays: usize) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        let rays = self.rays.fetch_add(rays, Ordering::Relaxed) + rays;
+        let interval = match self.reporting {
+            Reporting::Quiet => return,
+            Reporting::Terminal => TERMINAL_INTERVAL,
+            Reporting::Log => LOG_INTERVAL,
+        };
+
+        // Threads that find another one reporting just carry on.
+        if let Ok(mut last) = self.last_report.try_lock() {
+            let now = Instant::now();
+            if now - *last >= interval {
+                *last = now;
+                self.report(done, rays, now - self.start);
+            }
+        }
+    }
+
+    // Leaves the terminal line complete, the caller reports the result.
+    pub fn finish(&self) {
+        if self.reporting == Reporting::Terminal {
+            let rays = self.rays.load(Ordering::Relaxed);
+            self.report(self.done.load(Ordering::Relaxed), rays, self.start.elapsed());
+            eprintln!();
+        }
+    }
+
+    fn report(&self, done: usize, rays: usize, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let line = format!(
+            "{:5.1}% ({}/{} tiles), {} rays/s, {}",
+            100.0 * done as f64 / self.total.max(1) as f64,
+            done,
+            self.total,
+            format_rate(rays as f64 / seconds.max(1e-9)),
+            if done >= self.total {
+                format!("done in {}", format_duration(seconds))
+            } else if done == 0 {
+                "ETA unknown".to_string()
+            } else {
+                format!("ETA {}", format_duration(seconds * (self.total - done) as f64 / done as f64))
+            },
+        );
+
+        let mut stderr = io::stderr();
+        let _ = match self.reporting {
+            // Return to the start of the line and clear it.
+            Reporting::Terminal => write!(stderr, "\r{}\x1b[K", line),
+            _ => writeln!(stderr, "Rendered {}", line),
+        };
+        let _ = stderr.flush();
+    }
+}
+
+// Seconds as m:ss, or h:mm:ss from an hour on.
+pub fn format_duration(seconds: f64) -> String {
+    let s = seconds.round() as u64;
+    if s >= 3600 {
+        format!("{}:{:02}:{:02}", s / 3600, s / 60 % 60, s % 60)
+    } else {
+        format!("{}:{:02}", s / 60, s % 60)
+    }
+}
+
+pub fn format_rate(rate: f64) -> String {
+    if rate >= 1e9 {
+        format!("{:.2}G", rate / 1e9)
+    } else if rate >= 1e6 {
+        format!("{:.2}M", rate / 1e6)
+    } else if rate >= 1e3 {
+        format!("{:.1}k", rate / 1e3)
+    } else {
+        format!("{:.0}", rate)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0.0), "0:00");
+        assert_eq!(format_duration(59.6), "1:00");
+        assert_eq!(format_duration(754.0), "12:34");
+        assert_eq!(format_duration(3600.0 + 62.0), "1:01:02");
+    }
+
+    #[test]
+    fn test_format_rate() {
+        assert_eq!(format_rate(12.3), "12");
+        assert_eq!(format_rate(4567.0), "4.6k");
+        assert_eq!(format_rate(1234567.0), "1.23M");
+        assert_eq!(format_rate(2.5e9), "2.50G");
+    }
+
+    #[test]
+    fn test_tile_done() {
+        let p = Progress::new(Reporting::Quiet, 3);
+        p.tile_done(10);
+        p.tile_done(20);
+        assert_eq!(p.done.load(Ordering::Relaxed), 2);
+        assert_eq!(p.rays.load(Ordering::Relaxed), 30);
+    }
+}
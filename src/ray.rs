@@ -1,4 +1,4 @@
-use crate::linalg::matrix::Matrix;
+use crate::linalg::matrix4::Matrix4;
 use crate::linalg::tuple::Tuple;
 
 
@@ -6,6 +6,9 @@ use crate::linalg::tuple::Tuple;
 pub struct Ray {
     pub origin: Tuple,
     pub direction: Tuple,
+    // Moment within the shutter interval the ray is looking at, moving
+    // objects are intersected in their position at that time.
+    pub time: f64,
 }
 
 impl Ray {
@@ -13,23 +16,25 @@ impl Ray {
         Ray {
             origin: Tuple::point(ox, oy, oz),
             direction: Tuple::vector(dx, dy, dz),
+            time: 0.0,
         }
     }
 
     pub fn position(&self, t: f64) -> Tuple {
-        self.origin.add(&self.direction.multiply(t))
+        self.origin + self.direction * t
     }
 
-    pub fn transform(&self, m: &Matrix) -> Self {
+    pub fn transform(&self, m: &Matrix4) -> Self {
         Ray {
-            origin: m.multiply_tuple(&self.origin),
-            direction: m.multiply_tuple(&self.direction),
+            origin: *m * self.origin,
+            direction: *m * self.direction,
+            time: self.time,
         }
     }
 }
 
 pub fn reflect(ray: &Tuple, normal: &Tuple) -> Tuple {
-    ray.subtract(&normal.multiply(2.0).multiply(ray.dot(normal)))
+    *ray - *normal * 2.0 * ray.dot(normal)
 }
 
 
@@ -76,7 +81,7 @@ mod tests {
     #[test]
     fn test_transformed_intersect() {
         let r = Ray::new(0.0, 0.0, -5.0, 0.0, 0.0, 1.0);
-        let m = Matrix::scaling(2.0, 2.0, 2.0);
+        let m = Matrix4::scaling(2.0, 2.0, 2.0);
         let s: Box<dyn Shape + Sync> = Box::new(Sphere {
             id: Uuid::new_v4(),
             inv_transformation_transposed: m.invert().transpose(),
@@ -89,7 +94,7 @@ mod tests {
         assert_eq!(7.0, x[1].t);
 
         let r = Ray::new(0.0, 0.0, -5.0, 0.0, 0.0, 1.0);
-        let m = Matrix::translation(5.0, 0.0, 0.0);
+        let m = Matrix4::translation(5.0, 0.0, 0.0);
         let s: Box<dyn Shape + Sync> = Box::new(Sphere {
             id: Uuid::new_v4(),
             inv_transformation_transposed: m.invert().transpose(),
@@ -104,7 +109,7 @@ mod tests {
 
     #[test]
     fn test_sphere_normal_at() {
-        let m = Matrix::translation(0.0, 1.0, 0.0);
+        let m = Matrix4::translation(0.0, 1.0, 0.0);
         let s: Box<dyn Shape + Sync> = Box::new(Sphere {
             id: Uuid::new_v4(),
             inv_transformation_transposed: m.invert().transpose(),
@@ -118,8 +123,7 @@ mod tests {
         assert_eq!(s.normal_at(&p), expected);
 
 
-        let m = Matrix::scaling(1.0, 0.5, 1.0)
-            .multiply_matrix(&Matrix::rotation_z(144.0));
+        let m = Matrix4::scaling(1.0, 0.5, 1.0) * Matrix4::rotation_z(144.0);
         let s: Box<dyn Shape + Sync> = Box::new(Sphere {
             id: Uuid::new_v4(),
             inv_transformation_transposed: m.invert().transpose(),
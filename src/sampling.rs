@@ -0,0 +1,381 @@
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+
+// Where the samples of a pixel are placed, as offsets in the unit
+// square. Every pixel gets its own random jitter, scrambling or
+// rotation, seeded by its index so renders are reproducible.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SamplePattern {
+    // Regular grid, the sample count is rounded up to fill it.
+    Grid,
+    // One random sample in every cell of the grid.
+    Jittered,
+    // Halton sequence in bases 2 and 3, randomly rotated per pixel.
+    Halton,
+    // The first two Sobol dimensions, scrambled per pixel.
+    Sobol,
+}
+
+impl SamplePattern {
+    pub fn from_name(name: &str) -> SamplePattern {
+        match name {
+            "grid" => SamplePattern::Grid,
+            "jittered" => SamplePattern::Jittered,
+            "halton" => SamplePattern::Halton,
+            "sobol" => SamplePattern::Sobol,
+            _ => panic!("Undefined sample pattern: {:?}!", name),
+        }
+    }
+
+    pub fn samples(&self, n: usize, seed: u64) -> Vec<(f64, f64)> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        match self {
+            SamplePattern::Grid | SamplePattern::Jittered => {
+                let rows = ((n as f64).sqrt().floor() as usize).max(1);
+                let cols = n.div_ceil(rows);
+                let mut result = Vec::new();
+                for i in 0..rows {
+                    for j in 0..cols {
+                        let (du, dv) = match self {
+                            SamplePattern::Grid => (0.5, 0.5),
+                            _ => (rng.gen::<f64>(), rng.gen::<f64>()),
+                        };
+                        result.push(((j as f64 + du) / cols as f64, (i as f64 + dv) / rows as f64));
+                    }
+                }
+                result
+            },
+            SamplePattern::Halton => {
+                let (ru, rv) = (rng.gen::<f64>(), rng.gen::<f64>());
+                (0..n).map(|i| {
+                    let u = radical_inverse(i as u64 + 1, 2) + ru;
+                    let v = radical_inverse(i as u64 + 1, 3) + rv;
+                    (u.fract(), v.fract())
+                }).collect()
+            },
+            SamplePattern::Sobol => {
+                let (su, sv) = (rng.gen::<u32>(), rng.gen::<u32>());
+                (0..n as u32).map(|i| {
+                    (to_unit(i.reverse_bits() ^ su), to_unit(sobol_second(i) ^ sv))
+                }).collect()
+            },
+        }
+    }
+}
+
+fn radical_inverse(mut i: u64, base: u64) -> f64 {
+    let mut result = 0.0;
+    let mut f = 1.0 / base as f64;
+    while i > 0 {
+        result += (i % base) as f64 * f;
+        i /= base;
+        f /= base as f64;
+    }
+    result
+}
+
+// Second dimension of the Sobol sequence, its generator matrix is the
+// Pascal triangle mod 2.
+fn sobol_second(mut i: u32) -> u32 {
+    let mut result = 0;
+    let mut v = 1 << 31;
+    while i != 0 {
+        if i & 1 == 1 {
+            result ^= v;
+        }
+        i >>= 1;
+        v ^= v >> 1;
+    }
+    result
+}
+
+fn to_unit(bits: u32) -> f64 {
+    bits as f64 / 4294967296.0
+}
+
+// SplitMix64 finaliser, for cheap reproducible random numbers derived
+// from a position instead of a generator.
+pub fn hash(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+// Two numbers in [0, 1) from a hash.
+pub fn hash_to_unit(x: u64) -> (f64, f64) {
+    let h = hash(x);
+    (to_unit((h >> 32) as u32), to_unit(h as u32))
+}
+
+
+// Shape of a lens aperture, the blades of a real lens give out of focus
+// highlights a polygonal shape.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ApertureShape {
+    Disk,
+    Polygon(u32),
+}
+
+impl ApertureShape {
+    // Maps a point of the unit square uniformly onto the aperture of
+    // radius 1 (for polygons the radius of the circumcircle).
+    pub fn sample(&self, u: f64, v: f64) -> (f64, f64) {
+        match self {
+            ApertureShape::Disk => {
+                // Shirley's concentric mapping keeps strata intact.
+                let (a, b) = (2.0 * u - 1.0, 2.0 * v - 1.0);
+                if a == 0.0 && b == 0.0 {
+                    return (0.0, 0.0);
+                }
+                let quarter = std::f64::consts::FRAC_PI_4;
+                let (r, phi) = if a.abs() > b.abs() {
+                    (a, quarter * (b / a))
+                } else {
+                    (b, 2.0 * quarter - quarter * (a / b))
+                };
+                (r * phi.cos(), r * phi.sin())
+            },
+            ApertureShape::Polygon(blades) => {
+                // Pick one of the triangles between the centre and two
+                // neighbouring corners, then a point inside it.
+                let n = *blades as f64;
+                let k = (u * n).floor().min(n - 1.0);
+                let w = u * n - k;
+                let angle = 2.0 * std::f64::consts::PI / n;
+                let (a, b) = ((k * angle).cos(), (k * angle).sin());
+                let (c, d) = (((k + 1.0) * angle).cos(), ((k + 1.0) * angle).sin());
+                let s = v.sqrt();
+                (s * ((1.0 - w) * a + w * c), s * ((1.0 - w) * b + w * d))
+            },
+        }
+    }
+}
+
+
+// Reconstruction filter weighting the samples of a pixel by their
+// distance from its centre, in pixels. Filters are separable, the 2D
+// weight is the product of the weights in x and y.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Filter {
+    Box,
+    Tent,
+    Gaussian,
+    // Mitchell-Netravali with B = C = 1/3, sharper than the Gaussian but
+    // with slightly negative lobes.
+    Mitchell,
+}
+
+impl Filter {
+    pub fn from_name(name: &str) -> Filter {
+        match name {
+            "box" => Filter::Box,
+            "tent" => Filter::Tent,
+            "gaussian" => Filter::Gaussian,
+            "mitchell" => Filter::Mitchell,
+            _ => panic!("Undefined filter: {:?}!", name),
+        }
+    }
+
+    pub fn radius(&self) -> f64 {
+        match self {
+            Filter::Box => 0.5,
+            Filter::Tent => 1.0,
+            Filter::Gaussian => 1.5,
+            Filter::Mitchell => 2.0,
+        }
+    }
+
+    pub fn weight_1d(&self, x: f64) -> f64 {
+        let x = x.abs();
+        if x > self.radius() {
+            return 0.0;
+        }
+        match self {
+            Filter::Box => 1.0,
+            Filter::Tent => 1.0 - x,
+            Filter::Gaussian => {
+                // Shifted down so it reaches zero at the radius.
+                let g = |x: f64| (-2.0 * x * x).exp();
+                g(x) - g(self.radius())
+            },
+            Filter::Mitchell => {
+                let (b, c) = (1.0 / 3.0, 1.0 / 3.0);
+                let result = if x < 1.0 {
+                    (12.0 - 9.0 * b - 6.0 * c) * x * x * x
+                        + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+                        + (6.0 - 2.0 * b)
+                } else {
+                    (-b - 6.0 * c) * x * x * x
+                        + (6.0 * b + 30.0 * c) * x * x
+                        + (-12.0 * b - 48.0 * c) * x
+                        + (8.0 * b + 24.0 * c)
+                };
+                result / 6.0
+            },
+        }
+    }
+}
+
+
+// Samples are spread over the whole support of the filter, so wider
+// filters blend in some of the neighbouring pixels. Rather than
+// weighting uniform samples, they are warped to be distributed like the
+// filter (its absolute value, to allow negative lobes) and only weighted
+// by its sign, which wastes no samples in the tails.
+#[derive(Debug, Clone)]
+pub struct FilterSampler {
+    pub filter: Filter,
+    cdf: Vec<f64>,
+}
+
+const FILTER_BINS: usize = 256;
+
+impl FilterSampler {
+    pub fn new(filter: Filter) -> FilterSampler {
+        let r = filter.radius();
+        let width = 2.0 * r / FILTER_BINS as f64;
+        let mut cdf = vec![0.0];
+        for i in 0..FILTER_BINS {
+            let x = -r + (i as f64 + 0.5) * width;
+            cdf.push(cdf[i] + filter.weight_1d(x).abs());
+        }
+        let total = cdf[FILTER_BINS];
+        for c in cdf.iter_mut() {
+            *c /= total;
+        }
+        FilterSampler {
+            filter: filter,
+            cdf: cdf,
+        }
+    }
+
+    fn sample_1d(&self, u: f64) -> f64 {
+        let i = (self.cdf.partition_point(|&c| c <= u) - 1).min(FILTER_BINS - 1);
+        let t = (u - self.cdf[i]) / (self.cdf[i + 1] - self.cdf[i]);
+        let r = self.filter.radius();
+        -r + (i as f64 + t) * 2.0 * r / FILTER_BINS as f64
+    }
+
+    // Offset from the pixel centre and weight of the sample for a point
+    // in the unit square.
+    pub fn sample(&self, u: f64, v: f64) -> (f64, f64, f64) {
+        let dx = self.sample_1d(u);
+        let dy = self.sample_1d(v);
+        let sign = |w: f64| if w < 0.0 { -1.0 } else { 1.0 };
+        (dx, dy, sign(self.filter.weight_1d(dx)) * sign(self.filter.weight_1d(dy)))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::equal;
+
+    #[test]
+    fn test_grid() {
+        let s = SamplePattern::Grid.samples(4, 0);
+        assert_eq!(s, vec![(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)]);
+        assert_eq!(SamplePattern::Grid.samples(1, 0), vec![(0.5, 0.5)]);
+        assert_eq!(SamplePattern::Grid.samples(5, 0).len(), 6);
+    }
+
+    #[test]
+    fn test_stratified() {
+        // Each pattern puts exactly one of 16 samples into every cell of
+        // a 4x4 grid, and different pixels get different samples.
+        for &p in [SamplePattern::Jittered, SamplePattern::Halton, SamplePattern::Sobol].iter() {
+            let s = p.samples(16, 42);
+            assert_eq!(s.len(), 16);
+            assert!(s.iter().all(|&(u, v)| u >= 0.0 && u < 1.0 && v >= 0.0 && v < 1.0));
+            if p != SamplePattern::Halton {
+                let mut cells: Vec<usize> = s.iter()
+                    .map(|&(u, v)| (v * 4.0) as usize * 4 + (u * 4.0) as usize)
+                    .collect();
+                cells.sort();
+                assert_eq!(cells, (0..16).collect::<Vec<usize>>());
+            }
+            assert_eq!(s, p.samples(16, 42));
+            assert_ne!(s, p.samples(16, 43));
+        }
+    }
+
+    #[test]
+    fn test_sequences() {
+        assert_eq!(radical_inverse(1, 2), 0.5);
+        assert_eq!(radical_inverse(6, 2), 0.375);
+        assert!(equal(radical_inverse(5, 3), 7.0 / 9.0));
+
+        let v: Vec<f64> = (0..4).map(|i| to_unit(sobol_second(i))).collect();
+        assert_eq!(v, vec![0.0, 0.5, 0.75, 0.25]);
+    }
+
+    #[test]
+    fn test_aperture() {
+        let n = 32;
+        for i in 0..n {
+            for j in 0..n {
+                let (u, v) = (i as f64 / n as f64, j as f64 / n as f64);
+                let (x, y) = ApertureShape::Disk.sample(u, v);
+                assert!(x * x + y * y <= 1.0 + 1e-9);
+
+                // Inside a hexagon: on the inner side of every edge.
+                let (x, y) = ApertureShape::Polygon(6).sample(u, v);
+                for k in 0..6 {
+                    let a = k as f64 * std::f64::consts::PI / 3.0;
+                    let b = (k + 1) as f64 * std::f64::consts::PI / 3.0;
+                    let cross = (b.cos() - a.cos()) * (y - a.sin()) - (b.sin() - a.sin()) * (x - a.cos());
+                    assert!(cross >= -1e-9);
+                }
+            }
+        }
+        assert_eq!(ApertureShape::Disk.sample(0.5, 0.5), (0.0, 0.0));
+        assert_eq!(ApertureShape::Disk.sample(1.0, 0.5), (1.0, 0.0));
+    }
+
+    #[test]
+    fn test_hash_to_unit() {
+        let (u, v) = hash_to_unit(7);
+        assert!(u >= 0.0 && u < 1.0 && v >= 0.0 && v < 1.0);
+        assert_eq!(hash_to_unit(7), (u, v));
+        assert_ne!(hash_to_unit(8), (u, v));
+    }
+
+    #[test]
+    fn test_filters() {
+        for &f in [Filter::Box, Filter::Tent, Filter::Gaussian, Filter::Mitchell].iter() {
+            assert!(f.weight_1d(0.0) > 0.0);
+            assert_eq!(f.weight_1d(f.radius() + 0.1), 0.0);
+            assert_eq!(f.weight_1d(0.3), f.weight_1d(-0.3));
+        }
+        assert_eq!(Filter::Tent.weight_1d(0.25), 0.75);
+        assert!(equal(Filter::Mitchell.weight_1d(0.0), 8.0 / 9.0));
+        assert!(equal(Filter::Mitchell.weight_1d(1.0), 1.0 / 18.0));
+        assert!(equal(Filter::Mitchell.weight_1d(2.0), 0.0));
+        assert!(Filter::Mitchell.weight_1d(1.5) < 0.0);
+    }
+
+    #[test]
+    fn test_filter_sampler() {
+        let box_filter = FilterSampler::new(Filter::Box);
+        assert_eq!(box_filter.sample(0.0, 0.5), (-0.5, 0.0, 1.0));
+        assert!(equal(box_filter.sample(0.75, 0.25).0, 0.25));
+
+        // Tent: the inverse of its CDF, mirrored at the centre.
+        let tent = FilterSampler::new(Filter::Tent);
+        assert!(equal(tent.sample(0.125, 0.5).0, -0.5));
+        assert!(equal(tent.sample(0.875, 0.5).0, 0.5));
+
+        // Samples of the Mitchell filter land in the negative lobes as
+        // often as their share of its absolute area.
+        let mitchell = FilterSampler::new(Filter::Mitchell);
+        let n = 10000;
+        let negative = (0..n)
+            .filter(|&i| mitchell.sample((i as f64 + 0.5) / n as f64, 0.5).2 < 0.0)
+            .count();
+        assert!(negative > 0 && negative < n / 10);
+        assert!((0..n).all(|i| mitchell.sample_1d(i as f64 / n as f64).abs() <= 2.0));
+    }
+}
@@ -1,7 +1,10 @@
 use std::collections::HashMap;
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use crate::camera::Camera;
+use crate::animation::SceneAnimation;
+use crate::camera::{Adaptive, Camera, Convergence, Projection, Stereo, StereoLayout};
 use crate::color::Color;
 use crate::patterns::{
     pattern::Pattern,
@@ -11,13 +14,36 @@ use crate::patterns::{
     stripe::StripePattern
 };
 use crate::light::PointLight;
-use crate::linalg::matrix::Matrix;
+use crate::linalg::matrix4::Matrix4;
 use crate::linalg::tuple::Tuple;
 use crate::material::Material;
-use crate::shapes::{shape::Shape, sphere::Sphere, plane::Plane, cube::Cube, cylinder::Cylinder, triangle::Triangle};
+use crate::obj;
+use crate::sampling::{ApertureShape, Filter, FilterSampler, SamplePattern};
+use crate::sdf::Sdf;
+use crate::shapes::{
+    shape::Shape,
+    sphere::Sphere,
+    plane::Plane,
+    cone::Cone,
+    cube::Cube,
+    csg::{Csg, CsgOperation},
+    cylinder::Cylinder,
+    disk::Disk,
+    group::Group,
+    heightfield,
+    implicit::Implicit,
+    moving::{Keyframes, Moving},
+    quad::Quad,
+    rectangle::Rectangle,
+    smooth_triangle::SmoothTriangle,
+    torus::Torus,
+    triangle::Triangle
+};
+use crate::tiles::TileOrder;
 use crate::world::World;
 
 use serde::{Deserialize};
+use serde_json::Value;
 
 
 #[derive(Clone, Debug, Deserialize)]
@@ -28,6 +54,37 @@ pub struct SceneCamera {
     pub from: Vec<f64>,
     pub to: Vec<f64>,
     pub up: Vec<f64>,
+    pub projection: Option<String>,
+
+    // Anti-aliasing parameters
+    pub samples: Option<usize>,
+    pub sample_pattern: Option<String>,
+    pub filter: Option<String>,
+    pub adaptive_threshold: Option<f64>,
+    pub adaptive_depth: Option<u32>,
+
+    // Depth of field parameters
+    pub aperture: Option<f64>,
+    pub aperture_blades: Option<u32>,
+    pub focal_distance: Option<f64>,
+
+    // Rendering parameters
+    pub tile_size: Option<usize>,
+    pub tile_order: Option<String>,
+
+    // Motion blur parameters
+    pub shutter_open: Option<f64>,
+    pub shutter_close: Option<f64>,
+
+    // Stereo parameters, the layout turns stereo rendering on
+    pub stereo: Option<String>,
+    pub interocular_distance: Option<f64>,
+    pub convergence: Option<String>,
+
+    // Output parameters
+    pub tone_mapping: Option<String>,
+    pub exposure: Option<f64>,
+    pub dithering: Option<String>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -68,15 +125,41 @@ pub struct SceneTransformation {
 pub struct SceneObject {
     pub shape: String,
 
-    // Cylinder specific parameters
+    // Cylinder and cone specific parameters
     pub min_y: Option<f64>,
     pub max_y: Option<f64>,
     pub closed: Option<bool>,
 
-    // Triangle specific parameters
+    // Disk specific parameters
+    pub radius: Option<f64>,
+    pub inner_radius: Option<f64>,
+
+    // Torus specific parameters
+    pub major_radius: Option<f64>,
+    pub minor_radius: Option<f64>,
+
+    // Triangle and quad specific parameters
     pub p1: Option<Vec<f64>>,
     pub p2: Option<Vec<f64>>,
     pub p3: Option<Vec<f64>>,
+    pub n1: Option<Vec<f64>>,
+    pub n2: Option<Vec<f64>>,
+    pub n3: Option<Vec<f64>>,
+
+    // Group specific parameters
+    pub children: Option<HashMap<String, SceneObject>>,
+
+    // Mesh and heightfield specific parameters
+    pub file: Option<String>,
+    pub scale: Option<Vec<f64>>,
+
+    // CSG specific parameters
+    pub operation: Option<String>,
+    pub left: Option<Box<SceneObject>>,
+    pub right: Option<Box<SceneObject>>,
+
+    // Implicit surface specific parameters
+    pub sdf: Option<SceneSdf>,
 
     pub material: Option<String>,
     pub color: Option<Vec<f64>>,
@@ -88,6 +171,37 @@ pub struct SceneObject {
     pub shininess: Option<f64>,
     pub transparency: Option<f64>,
     pub refractive_index: Option<f64>,
+    pub transformations: Option<Vec<SceneTransformation>>,
+
+    // Motion parameters, an object moves from its transformations at
+    // time 0 to its end transformations at time 1, or along keyframes.
+    pub end_transformations: Option<Vec<SceneTransformation>>,
+    pub keyframes: Option<Vec<SceneKeyframe>>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SceneKeyframe {
+    pub time: f64,
+    pub transformations: Vec<SceneTransformation>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SceneSdf {
+    pub function: String,
+
+    // Primitive parameters
+    pub radius: Option<f64>,
+    pub size: Option<Vec<f64>>,
+    pub major_radius: Option<f64>,
+    pub minor_radius: Option<f64>,
+    pub a: Option<Vec<f64>>,
+    pub b: Option<Vec<f64>>,
+
+    // Combinator parameters
+    pub children: Option<Vec<SceneSdf>>,
+    pub smoothness: Option<f64>,
+    pub period: Option<Vec<f64>>,
+
     pub transformations: Option<Vec<SceneTransformation>>
 }
 
@@ -98,19 +212,33 @@ pub struct Scene {
     pub patterns: Option<HashMap<String, ScenePattern>>,
     pub materials: Option<HashMap<String, SceneMaterial>>,
     pub transformations: Option<HashMap<String, Vec<SceneTransformation>>>,
-    pub objects: Option<HashMap<String, SceneObject>>
+    pub objects: Option<HashMap<String, SceneObject>>,
+    pub animation: Option<SceneAnimation>,
+
+    // Directory of the scene file, files referenced by the scene are
+    // resolved relative to it.
+    #[serde(skip)]
+    pub directory: PathBuf,
 }
 
 
-pub fn parse_scene(source: &String) -> Scene {
+// Scene as it is at the given frame of its animation, animated values
+// are set in the document before it is read like any other scene.
+pub fn parse_scene(source: &String, frame: f64) -> Scene {
     let contents = fs::read_to_string(source)
         .expect("Could not read the scene file!");
-    let result: Scene = serde_json::from_str(&contents).unwrap();
+    let mut document: Value = serde_json::from_str(&contents).unwrap();
+    if let Some(animation) = document.get("animation") {
+        let animation: SceneAnimation = serde_json::from_value(animation.clone()).unwrap();
+        document = animation.apply(&document, frame);
+    }
+    let mut result: Scene = serde_json::from_value(document).unwrap();
+    result.directory = Path::new(source).parent().unwrap_or(Path::new("")).to_path_buf();
     result
 }
 
 pub fn make_camera(scene: &Scene) -> Camera {
-    Camera::new(
+    let mut result = Camera::new(
         scene.camera.width,
         scene.camera.height,
         scene.camera.field_of_view,
@@ -129,37 +257,93 @@ pub fn make_camera(scene: &Scene) -> Camera {
             scene.camera.up[1],
             scene.camera.up[2],
         )
-    )
+    );
+    if let Some(name) = &scene.camera.projection {
+        result.projection = Projection::from_name(name);
+    }
+    if let Some(n) = scene.camera.samples {
+        result.samples = n;
+    }
+    if let Some(name) = &scene.camera.sample_pattern {
+        result.sample_pattern = SamplePattern::from_name(name);
+    }
+    if let Some(name) = &scene.camera.filter {
+        result.filter = FilterSampler::new(Filter::from_name(name));
+    }
+    if let Some(threshold) = scene.camera.adaptive_threshold {
+        result.adaptive = Some(Adaptive {
+            threshold: threshold,
+            max_depth: scene.camera.adaptive_depth.unwrap_or(2),
+        });
+    }
+    if let Some(aperture) = scene.camera.aperture {
+        result.aperture = aperture;
+    }
+    if let Some(blades) = scene.camera.aperture_blades {
+        if blades < 3 {
+            panic!("An aperture needs at least three blades: {:?}!", blades);
+        }
+        result.aperture_shape = ApertureShape::Polygon(blades);
+    }
+    if let Some(distance) = scene.camera.focal_distance {
+        result.focal_distance = distance;
+    }
+    if let Some(size) = scene.camera.tile_size {
+        result.tile_size = size;
+    }
+    if let Some(name) = &scene.camera.tile_order {
+        result.tile_order = TileOrder::from_name(name);
+    }
+    if let Some(time) = scene.camera.shutter_open {
+        result.shutter_open = time;
+        result.shutter_close = time;
+    }
+    if let Some(time) = scene.camera.shutter_close {
+        result.shutter_close = time;
+    }
+    if let Some(layout) = &scene.camera.stereo {
+        result.stereo = Some(Stereo {
+            layout: StereoLayout::from_name(layout),
+            // A thirtieth of the distance to the focal plane gives a
+            // comfortable depth.
+            interocular_distance: scene.camera.interocular_distance
+                .unwrap_or(result.focal_distance / 30.0),
+            convergence: Convergence::from_name(
+                scene.camera.convergence.as_ref().map_or("off-axis", |n| n.as_str())
+            ),
+        });
+    }
+    result
 }
 
-pub fn make_transformation(transformations: &Vec<SceneTransformation>, map: &HashMap<String, Matrix>) -> Matrix {
-    let mut result = Matrix::identity(4);
+pub fn make_transformation(transformations: &Vec<SceneTransformation>, map: &HashMap<String, Matrix4>) -> Matrix4 {
+    let mut result = Matrix4::identity();
 
     for cur in transformations.iter() {
 
         let next = match &cur.defined_transformation {
-            Some(key) => map[key].clone(),
+            Some(key) => map[key],
             None => {
                 let p = cur.parameters.as_ref().unwrap();
                 match cur.transformation.as_ref().unwrap().as_str() {
-                    "translate" => Matrix::translation(p[0], p[1], p[2]),
-                    "scale" => Matrix::scaling(p[0], p[1], p[2]),
-                    "rotate-x" => Matrix::rotation_x(p[0]),
-                    "rotate-y" => Matrix::rotation_y(p[0]),
-                    "rotate-z" => Matrix::rotation_z(p[0]),
-                    "shear" => Matrix::shearing(p[0], p[1], p[2], p[3], p[4], p[5]),
+                    "translate" => Matrix4::translation(p[0], p[1], p[2]),
+                    "scale" => Matrix4::scaling(p[0], p[1], p[2]),
+                    "rotate-x" => Matrix4::rotation_x(p[0]),
+                    "rotate-y" => Matrix4::rotation_y(p[0]),
+                    "rotate-z" => Matrix4::rotation_z(p[0]),
+                    "shear" => Matrix4::shearing(p[0], p[1], p[2], p[3], p[4], p[5]),
                     _ => panic!("Undefined transformation: {:?}!", cur.transformation)
                 }
             }
         };
 
-        result = result.multiply_matrix(&next);
+        result = result * next;
     }
 
     result
 }
 
-pub fn make_pattern(pat: &ScenePattern, map: &HashMap<String, Matrix>) -> Box<dyn Pattern + Sync> {
+pub fn make_pattern(pat: &ScenePattern, map: &HashMap<String, Matrix4>) -> Box<dyn Pattern + Sync> {
     let transformation = make_transformation(
         &pat.transformations.as_ref().unwrap(),
         map
@@ -192,6 +376,57 @@ pub fn make_pattern(pat: &ScenePattern, map: &HashMap<String, Matrix>) -> Box<dy
     result
 }
 
+pub fn make_sdf(value: &SceneSdf, map: &HashMap<String, Matrix4>) -> Sdf {
+    let children: Vec<Sdf> = value.children.as_ref().unwrap_or(&vec![]).iter()
+        .map(|c| make_sdf(c, map))
+        .collect();
+    let smoothness = value.smoothness.unwrap_or(0.0);
+
+    let result = match value.function.as_str() {
+        "sphere" => Sdf::Sphere { radius: value.radius.unwrap_or(1.0) },
+        "box" => Sdf::Box {
+            size: Tuple::vector_from_vec(value.size.as_ref().unwrap_or(&vec![1.0, 1.0, 1.0])),
+        },
+        "rounded-box" => Sdf::RoundedBox {
+            size: Tuple::vector_from_vec(value.size.as_ref().unwrap_or(&vec![1.0, 1.0, 1.0])),
+            radius: value.radius.unwrap_or(0.1),
+        },
+        "torus" => Sdf::Torus {
+            major_radius: value.major_radius.unwrap_or(1.0),
+            minor_radius: value.minor_radius.unwrap_or(0.25),
+        },
+        "capsule" => Sdf::Capsule {
+            a: Tuple::point_from_vec(value.a.as_ref().expect("Capsules need an end point a!")),
+            b: Tuple::point_from_vec(value.b.as_ref().expect("Capsules need an end point b!")),
+            radius: value.radius.unwrap_or(0.5),
+        },
+        "union" | "intersection" | "subtraction" => {
+            // Combine all children from left to right, a subtraction
+            // removes all further children from the first one.
+            let mut iter = children.into_iter();
+            let first = iter.next().expect("SDF combinations need children!");
+            iter.fold(first, |left, right| {
+                let (left, right) = (Arc::new(left), Arc::new(right));
+                match value.function.as_str() {
+                    "union" => Sdf::Union { left: left, right: right, smoothness: smoothness },
+                    "intersection" => Sdf::Intersection { left: left, right: right, smoothness: smoothness },
+                    _ => Sdf::Subtraction { left: left, right: right, smoothness: smoothness },
+                }
+            })
+        },
+        "repeat" => Sdf::Repetition {
+            child: Arc::new(children.into_iter().next().expect("SDF repetitions need a child!")),
+            period: Tuple::vector_from_vec(value.period.as_ref().expect("SDF repetitions need a period!")),
+        },
+        _ => panic!("Undefined distance function: {:?}!", value.function)
+    };
+
+    match value.transformations.as_ref() {
+        Some(t) => Sdf::transformed(result, make_transformation(t, map)),
+        None => result,
+    }
+}
+
 pub fn make_world(scene: &Scene) -> World {
     let mut lights: Vec<PointLight> = vec![];
 
@@ -203,7 +438,7 @@ pub fn make_world(scene: &Scene) -> World {
         lights.push(light);
     }
 
-    let mut transformations: HashMap<String, Matrix> = HashMap::new();
+    let mut transformations: HashMap<String, Matrix4> = HashMap::new();
     for (name, value) in scene.transformations.as_ref().unwrap_or(&HashMap::new()).iter() {
         let tmp = make_transformation(&value, &transformations);
         transformations.insert(name.clone(), tmp);
@@ -242,74 +477,171 @@ pub fn make_world(scene: &Scene) -> World {
 
     let mut objects: Vec<Box<dyn Shape + Sync>> = vec![];
     for (_, value) in scene.objects.as_ref().unwrap_or(&HashMap::new()).iter() {
-        let mut m = match &value.material {
-            Some(key) => if let Some(k) = materials.get(key) {
-                k.clone()
-            } else {
-                panic!("Undefined material: {:?}", key);
-            },
-            None => Material::default(),
-        };
-        // TODO: abstract color/pattern assignment.
-        if let Some(v) = value.color.as_ref() {
-            m.color = Some(Color::new(v[0], v[1], v[2]));
+        objects.push(make_object(value, &materials, &patterns, &transformations, &scene.directory));
+    }
+
+    World::new(objects, lights)
+}
+
+pub fn make_object(
+    value: &SceneObject,
+    materials: &HashMap<String, Material>,
+    patterns: &HashMap<String, Box<dyn Pattern + Sync>>,
+    transformations: &HashMap<String, Matrix4>,
+    directory: &Path
+) -> Box<dyn Shape + Sync> {
+    let mut m = match &value.material {
+        Some(key) => if let Some(k) = materials.get(key) {
+            k.clone()
         } else {
-            // TODO: should allow directly defined patterns as well.
-            if let Some(v) = value.pattern.as_ref() {
-                m.color = None;
-                m.pattern = Some(patterns[v].clone());
-            }
+            panic!("Undefined material: {:?}", key);
+        },
+        None => Material::default(),
+    };
+    // TODO: abstract color/pattern assignment.
+    if let Some(v) = value.color.as_ref() {
+        m.color = Some(Color::new(v[0], v[1], v[2]));
+    } else {
+        // TODO: should allow directly defined patterns as well.
+        if let Some(v) = value.pattern.as_ref() {
+            m.color = None;
+            m.pattern = Some(patterns[v].clone());
         }
+    }
 
-        m.ambient = value.ambient.unwrap_or(m.ambient);
-        m.diffuse = value.diffuse.unwrap_or(m.diffuse);
-        m.specular = value.specular.unwrap_or(m.diffuse);
-        m.shininess = value.shininess.unwrap_or(m.shininess);
-        m.reflective = value.reflective.unwrap_or(m.reflective);
-        m.transparency = value.transparency.unwrap_or(m.transparency);
-        m.refractive_index = value.refractive_index.unwrap_or(m.refractive_index);
-
-
-        let mut object: Box<dyn Shape + Sync> = match value.shape.as_ref() {
-            "sphere" => Box::new(Sphere::default()),
-            "plane" => Box::new(Plane::default()),
-            "cube" => Box::new(Cube::default()),
-            "cylinder" => {
-                let mut c = Box::new(Cylinder::default());
-                c.min_y = value.min_y.unwrap_or(c.min_y);
-                c.max_y = value.max_y.unwrap_or(c.max_y);
-                c.closed = value.closed.unwrap_or(c.closed);
-                c
-            },
-            "triangle" => {
-                let p1 = value.p1.as_ref().unwrap();
-                let p2 = value.p2.as_ref().unwrap();
-                let p3 = value.p3.as_ref().unwrap();
-                Box::new(Triangle::new(
+    m.ambient = value.ambient.unwrap_or(m.ambient);
+    m.diffuse = value.diffuse.unwrap_or(m.diffuse);
+    m.specular = value.specular.unwrap_or(m.diffuse);
+    m.shininess = value.shininess.unwrap_or(m.shininess);
+    m.reflective = value.reflective.unwrap_or(m.reflective);
+    m.transparency = value.transparency.unwrap_or(m.transparency);
+    m.refractive_index = value.refractive_index.unwrap_or(m.refractive_index);
+
+
+    let mut object: Box<dyn Shape + Sync> = match value.shape.as_ref() {
+        "sphere" => Box::new(Sphere::default()),
+        "plane" => Box::new(Plane::default()),
+        "cube" => Box::new(Cube::default()),
+        "cylinder" => {
+            let mut c = Box::new(Cylinder::default());
+            c.min_y = value.min_y.unwrap_or(c.min_y);
+            c.max_y = value.max_y.unwrap_or(c.max_y);
+            c.closed = value.closed.unwrap_or(c.closed);
+            c
+        },
+        "cone" => {
+            let mut c = Box::new(Cone::default());
+            c.min_y = value.min_y.unwrap_or(c.min_y);
+            c.max_y = value.max_y.unwrap_or(c.max_y);
+            c.closed = value.closed.unwrap_or(c.closed);
+            c
+        },
+        "torus" => {
+            let mut t = Box::new(Torus::default());
+            t.major_radius = value.major_radius.unwrap_or(t.major_radius);
+            t.minor_radius = value.minor_radius.unwrap_or(t.minor_radius);
+            t
+        },
+        "disk" => {
+            let mut d = Box::new(Disk::default());
+            d.radius = value.radius.unwrap_or(d.radius);
+            d.inner_radius = value.inner_radius.unwrap_or(d.inner_radius);
+            d
+        },
+        "rectangle" => Box::new(Rectangle::default()),
+        "quad" => Box::new(Quad::new(
+            Tuple::point_from_vec(value.p1.as_ref().expect("Quads need a corner p1!")),
+            Tuple::point_from_vec(value.p2.as_ref().expect("Quads need a corner p2!")),
+            Tuple::point_from_vec(value.p3.as_ref().expect("Quads need a corner p3!")),
+            Matrix4::identity(),
+            Material::default()
+        )),
+        "triangle" => {
+            let p1 = value.p1.as_ref().unwrap();
+            let p2 = value.p2.as_ref().unwrap();
+            let p3 = value.p3.as_ref().unwrap();
+            match (value.n1.as_ref(), value.n2.as_ref(), value.n3.as_ref()) {
+                (Some(n1), Some(n2), Some(n3)) => Box::new(SmoothTriangle::new(
+                    Tuple::point_from_vec(p1),
+                    Tuple::point_from_vec(p2),
+                    Tuple::point_from_vec(p3),
+                    [Tuple::vector_from_vec(n1), Tuple::vector_from_vec(n2), Tuple::vector_from_vec(n3)],
+                    Matrix4::identity(),
+                    Material::default()
+                )),
+                _ => Box::new(Triangle::new(
                     Tuple::point(p1[0], p1[1], p1[2]),
                     Tuple::point(p2[0], p2[1], p2[2]),
                     Tuple::point(p3[0], p3[1], p3[2]),
-                    Matrix::identity(4),
+                    Matrix4::identity(),
                     Material::default()
-                ))
-            },
-            _ => panic!("Undefined shape: {:?}!", value.shape)
-        };
+                )),
+            }
+        },
+        "group" => {
+            let mut children: Vec<Box<dyn Shape + Sync>> = vec![];
+            for (_, child) in value.children.as_ref().unwrap_or(&HashMap::new()).iter() {
+                children.push(make_object(child, materials, patterns, transformations, directory));
+            }
+            Box::new(Group::new(Matrix4::identity(), children))
+        },
+        "csg" => {
+            let operation = value.operation.as_ref().expect("CSG objects need an operation!");
+            let left = value.left.as_ref().expect("CSG objects need a left object!");
+            let right = value.right.as_ref().expect("CSG objects need a right object!");
+            Box::new(Csg::new(
+                CsgOperation::from_name(operation),
+                make_object(left, materials, patterns, transformations, directory),
+                make_object(right, materials, patterns, transformations, directory),
+                Matrix4::identity()
+            ))
+        },
+        "implicit" => {
+            let sdf = value.sdf.as_ref().expect("Implicit objects need a distance function!");
+            Box::new(Implicit::new(Matrix4::identity(), Material::default(), make_sdf(sdf, transformations)))
+        },
+        "mesh" => {
+            let file = value.file.as_ref().expect("Mesh objects need a file!");
+            Box::new(obj::load_obj(&directory.join(file)))
+        },
+        "heightfield" => {
+            let file = value.file.as_ref().expect("Heightfield objects need a file!");
+            let scale = value.scale.as_ref().map(Tuple::vector_from_vec).unwrap_or(Tuple::vector(1.0, 1.0, 1.0));
+            Box::new(heightfield::load_heightfield(&directory.join(file), scale))
+        },
+        _ => panic!("Undefined shape: {:?}!", value.shape)
+    };
 
+    // Children of groups and CSG shapes keep their own materials.
+    if value.shape != "group" && value.shape != "csg" {
         object.set_material(m);
-        let tmp = make_transformation(
-            &value.transformations.as_ref().unwrap_or(&vec![]),
-            &transformations
-        );
-        object.set_transformation(tmp);
-
-        objects.push(object);
     }
+    let tmp = make_transformation(
+        &value.transformations.as_ref().unwrap_or(&vec![]),
+        transformations
+    );
 
-    let world = World {
-        objects: objects,
-        lights: lights,
+    // The transformations of an object with keyframes apply on top of
+    // them, like those of a group around it.
+    let keyframes = match (&value.end_transformations, &value.keyframes) {
+        (Some(_), Some(_)) => panic!("Objects move either to end transformations or along keyframes!"),
+        (Some(end), None) => Some((
+            vec![(0.0, tmp), (1.0, make_transformation(end, transformations))],
+            Matrix4::identity(),
+        )),
+        (None, Some(frames)) => Some((
+            frames.iter().map(|k| (k.time, make_transformation(&k.transformations, transformations))).collect(),
+            tmp,
+        )),
+        (None, None) => None,
     };
+    match keyframes {
+        Some((frames, t)) => {
+            object = Box::new(Moving::new(object, Keyframes::new(frames)));
+            object.set_transformation(t);
+        },
+        None => object.set_transformation(tmp),
+    }
 
-    world
+    object
 }
@@ -0,0 +1,256 @@
+use crate::linalg::matrix4::Matrix4;
+use crate::linalg::tuple::Tuple;
+use crate::shapes::bounds::BoundingBox;
+
+use std::sync::Arc;
+
+
+// Signed distance functions: negative inside, positive outside, and
+// never larger than the distance to the closest point on the surface,
+// which is all sphere tracing needs to step safely. Combinators and
+// transformations keep that bound, so a tree of them stays a valid
+// distance function.
+#[derive(Debug, Clone)]
+pub enum Sdf {
+    Sphere { radius: f64 },
+    // Box with the given half extents.
+    Box { size: Tuple },
+    // Box with the given half extents whose edges are rounded with radius.
+    RoundedBox { size: Tuple, radius: f64 },
+    // Torus in the xz plane like the torus shape.
+    Torus { major_radius: f64, minor_radius: f64 },
+    Capsule { a: Tuple, b: Tuple, radius: f64 },
+
+    // A smoothness of 0 gives the sharp variants of the combinations.
+    Union { left: Arc<Sdf>, right: Arc<Sdf>, smoothness: f64 },
+    Intersection { left: Arc<Sdf>, right: Arc<Sdf>, smoothness: f64 },
+    Subtraction { left: Arc<Sdf>, right: Arc<Sdf>, smoothness: f64 },
+    // Infinite repetition of the child with the given period along each
+    // axis, a period of 0 leaves the axis alone. Only exact if the child
+    // fits into a single cell.
+    Repetition { child: Arc<Sdf>, period: Tuple },
+    Transformed { child: Arc<Sdf>, transformation: Matrix4, inv_transformation: Matrix4, stretch: f64 },
+}
+
+impl Sdf {
+    pub fn transformed(child: Sdf, transformation: Matrix4) -> Sdf {
+        let inv_transformation = transformation.invert();
+        Sdf::Transformed {
+            child: Arc::new(child),
+            stretch: max_stretch(&inv_transformation),
+            transformation: transformation,
+            inv_transformation: inv_transformation,
+        }
+    }
+
+    pub fn distance(&self, p: &Tuple) -> f64 {
+        match self {
+            Sdf::Sphere { radius } => length(p.x, p.y, p.z) - radius,
+            Sdf::Box { size } => box_distance(p, size),
+            Sdf::RoundedBox { size, radius } => {
+                let inner = Tuple::vector(size.x - radius, size.y - radius, size.z - radius);
+                box_distance(p, &inner) - radius
+            },
+            Sdf::Torus { major_radius, minor_radius } => {
+                let radial = length(p.x, 0.0, p.z) - major_radius;
+                length(radial, p.y, 0.0) - minor_radius
+            },
+            Sdf::Capsule { a, b, radius } => {
+                let pa = *p - *a;
+                let ba = *b - *a;
+                let h = (pa.dot(&ba) / ba.dot(&ba)).clamp(0.0, 1.0);
+                (pa - ba * h).magnitude() - radius
+            },
+            Sdf::Union { left, right, smoothness } => {
+                smooth_min(left.distance(p), right.distance(p), *smoothness)
+            },
+            Sdf::Intersection { left, right, smoothness } => {
+                -smooth_min(-left.distance(p), -right.distance(p), *smoothness)
+            },
+            Sdf::Subtraction { left, right, smoothness } => {
+                -smooth_min(-left.distance(p), right.distance(p), *smoothness)
+            },
+            Sdf::Repetition { child, period } => {
+                let q = Tuple::point(
+                    repeat(p.x, period.x),
+                    repeat(p.y, period.y),
+                    repeat(p.z, period.z),
+                );
+                child.distance(&q)
+            },
+            Sdf::Transformed { child, inv_transformation, stretch, .. } => {
+                child.distance(&(*inv_transformation * *p)) / stretch
+            },
+        }
+    }
+
+    pub fn bounds(&self) -> BoundingBox {
+        match self {
+            Sdf::Sphere { radius } => cube_bounds(*radius),
+            Sdf::Box { size } | Sdf::RoundedBox { size, .. } => {
+                BoundingBox::new(Tuple::point(-size.x, -size.y, -size.z), Tuple::point(size.x, size.y, size.z))
+            },
+            Sdf::Torus { major_radius, minor_radius } => {
+                let outer = major_radius + minor_radius;
+                BoundingBox::new(
+                    Tuple::point(-outer, -minor_radius, -outer),
+                    Tuple::point(outer, *minor_radius, outer)
+                )
+            },
+            Sdf::Capsule { a, b, radius } => {
+                let mut result = BoundingBox::empty();
+                result.add_point(&a.translate(-radius, -radius, -radius));
+                result.add_point(&a.translate(*radius, *radius, *radius));
+                result.add_point(&b.translate(-radius, -radius, -radius));
+                result.add_point(&b.translate(*radius, *radius, *radius));
+                result
+            },
+            Sdf::Union { left, right, smoothness } => {
+                // The smooth blend bulges out by at most a quarter of
+                // the smoothness.
+                let s = smoothness / 4.0;
+                let b = left.bounds().merge(&right.bounds());
+                BoundingBox::new(b.min.translate(-s, -s, -s), b.max.translate(s, s, s))
+            },
+            Sdf::Intersection { left, .. } | Sdf::Subtraction { left, .. } => left.bounds(),
+            Sdf::Repetition { child, period } => {
+                if period.x != 0.0 || period.y != 0.0 || period.z != 0.0 {
+                    BoundingBox::infinite()
+                } else {
+                    child.bounds()
+                }
+            },
+            Sdf::Transformed { child, transformation, .. } => child.bounds().transform(transformation),
+        }
+    }
+}
+
+fn length(x: f64, y: f64, z: f64) -> f64 {
+    (x * x + y * y + z * z).sqrt()
+}
+
+fn cube_bounds(size: f64) -> BoundingBox {
+    BoundingBox::new(Tuple::point(-size, -size, -size), Tuple::point(size, size, size))
+}
+
+fn box_distance(p: &Tuple, size: &Tuple) -> f64 {
+    let qx = p.x.abs() - size.x;
+    let qy = p.y.abs() - size.y;
+    let qz = p.z.abs() - size.z;
+    let outside = length(qx.max(0.0), qy.max(0.0), qz.max(0.0));
+    let inside = qx.max(qy).max(qz).min(0.0);
+    outside + inside
+}
+
+fn repeat(x: f64, period: f64) -> f64 {
+    if period == 0.0 {
+        x
+    } else {
+        x - period * (x / period).round()
+    }
+}
+
+// Polynomial smooth minimum, blending the two distances where they are
+// less than smoothness apart.
+pub fn smooth_min(a: f64, b: f64, smoothness: f64) -> f64 {
+    if smoothness <= 0.0 {
+        return a.min(b);
+    }
+    let h = (smoothness - (a - b).abs()).max(0.0) / smoothness;
+    a.min(b) - h * h * smoothness / 4.0
+}
+
+// Largest factor by which the matrix stretches any vector, i.e. its
+// spectral norm, found by power iteration on MᵀM. Dividing distances
+// measured after applying the matrix by it keeps them conservative.
+fn max_stretch(m: &Matrix4) -> f64 {
+    let mut v = Tuple::vector(1.0, 1.0, 1.0).normalize();
+    let mut result = 0.0;
+    let mt = m.transpose();
+    for _ in 0..32 {
+        let mut w = mt * (*m * v);
+        w.w = 0.0;
+        let norm = w.magnitude();
+        if norm == 0.0 {
+            break;
+        }
+        result = norm.sqrt();
+        v = w / norm;
+    }
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::equal;
+
+    #[test]
+    fn test_primitives() {
+        let s = Sdf::Sphere { radius: 1.0 };
+        assert!(equal(s.distance(&Tuple::point(0.0, 0.0, 0.0)), -1.0));
+        assert!(equal(s.distance(&Tuple::point(0.0, 3.0, 0.0)), 2.0));
+
+        let b = Sdf::Box { size: Tuple::vector(1.0, 2.0, 3.0) };
+        assert!(equal(b.distance(&Tuple::point(0.0, 0.0, 0.0)), -1.0));
+        assert!(equal(b.distance(&Tuple::point(4.0, 0.0, 0.0)), 3.0));
+        assert!(equal(b.distance(&Tuple::point(2.0, 3.0, 0.0)), 2.0_f64.sqrt()));
+
+        let rb = Sdf::RoundedBox { size: Tuple::vector(1.0, 1.0, 1.0), radius: 0.5 };
+        assert!(equal(rb.distance(&Tuple::point(2.0, 0.0, 0.0)), 1.0));
+        assert!(equal(rb.distance(&Tuple::point(2.0, 2.0, 0.0)), 2.0_f64.sqrt() * 1.5 - 0.5));
+
+        let t = Sdf::Torus { major_radius: 1.0, minor_radius: 0.25 };
+        assert!(equal(t.distance(&Tuple::point(1.0, 0.0, 0.0)), -0.25));
+        assert!(equal(t.distance(&Tuple::point(0.0, 0.0, 0.0)), 0.75));
+
+        let c = Sdf::Capsule { a: Tuple::point(0.0, 0.0, 0.0), b: Tuple::point(0.0, 2.0, 0.0), radius: 0.5 };
+        assert!(equal(c.distance(&Tuple::point(1.0, 1.0, 0.0)), 0.5));
+        assert!(equal(c.distance(&Tuple::point(0.0, 3.0, 0.0)), 0.5));
+    }
+
+    #[test]
+    fn test_combinations() {
+        let a = Arc::new(Sdf::Sphere { radius: 1.0 });
+        let b = Arc::new(Sdf::transformed(Sdf::Sphere { radius: 1.0 }, Matrix4::translation(1.5, 0.0, 0.0)));
+        let p = Tuple::point(0.75, 1.0, 0.0);
+
+        let union = Sdf::Union { left: a.clone(), right: b.clone(), smoothness: 0.0 };
+        let smooth = Sdf::Union { left: a.clone(), right: b.clone(), smoothness: 0.5 };
+        assert!(smooth.distance(&p) < union.distance(&p));
+        assert!(equal(union.distance(&Tuple::point(-2.0, 0.0, 0.0)), 1.0));
+        assert!(equal(smooth.distance(&Tuple::point(-2.0, 0.0, 0.0)), 1.0));
+
+        let sub = Sdf::Subtraction { left: a.clone(), right: b.clone(), smoothness: 0.0 };
+        assert!(sub.distance(&Tuple::point(-0.5, 0.0, 0.0)) < 0.0);
+        assert!(sub.distance(&Tuple::point(0.75, 0.0, 0.0)) > 0.0);
+
+        let inter = Sdf::Intersection { left: a, right: b, smoothness: 0.0 };
+        assert!(inter.distance(&Tuple::point(0.75, 0.0, 0.0)) < 0.0);
+        assert!(inter.distance(&Tuple::point(-0.5, 0.0, 0.0)) > 0.0);
+    }
+
+    #[test]
+    fn test_repetition() {
+        let r = Sdf::Repetition {
+            child: Arc::new(Sdf::Sphere { radius: 1.0 }),
+            period: Tuple::vector(4.0, 0.0, 0.0),
+        };
+        assert!(equal(r.distance(&Tuple::point(8.0, 0.0, 0.0)), -1.0));
+        assert!(equal(r.distance(&Tuple::point(-6.0, 0.0, 0.0)), 1.0));
+        assert!(equal(r.distance(&Tuple::point(8.0, 3.0, 0.0)), 2.0));
+        assert!(!r.bounds().is_finite());
+    }
+
+    #[test]
+    fn test_transformed() {
+        let s = Sdf::transformed(Sdf::Sphere { radius: 1.0 }, Matrix4::scaling(2.0, 1.0, 1.0));
+        // Distances are never overestimated.
+        assert!(s.distance(&Tuple::point(3.0, 0.0, 0.0)) <= 1.0);
+        assert!(equal(s.distance(&Tuple::point(0.0, 3.0, 0.0)), 2.0));
+        let b = s.bounds();
+        assert_eq!(b.min, Tuple::point(-2.0, -1.0, -1.0));
+        assert_eq!(b.max, Tuple::point(2.0, 1.0, 1.0));
+    }
+}
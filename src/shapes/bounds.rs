@@ -0,0 +1,199 @@
+use crate::linalg::matrix4::Matrix4;
+use crate::linalg::tuple::Tuple;
+use crate::ray::Ray;
+use crate::utils::EPSILON;
+
+
+#[derive(Debug, Copy, Clone)]
+pub struct BoundingBox {
+    pub min: Tuple,
+    pub max: Tuple,
+}
+
+impl BoundingBox {
+    pub fn new(min: Tuple, max: Tuple) -> BoundingBox {
+        BoundingBox {
+            min: min,
+            max: max,
+        }
+    }
+
+    pub fn empty() -> BoundingBox {
+        BoundingBox::new(
+            Tuple::point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            Tuple::point(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        )
+    }
+
+    pub fn infinite() -> BoundingBox {
+        BoundingBox::new(
+            Tuple::point(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            Tuple::point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        )
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.min.x.is_finite() && self.min.y.is_finite() && self.min.z.is_finite() &&
+            self.max.x.is_finite() && self.max.y.is_finite() && self.max.z.is_finite()
+    }
+
+    pub fn add_point(&mut self, p: &Tuple) {
+        self.min = Tuple::point(self.min.x.min(p.x), self.min.y.min(p.y), self.min.z.min(p.z));
+        self.max = Tuple::point(self.max.x.max(p.x), self.max.y.max(p.y), self.max.z.max(p.z));
+    }
+
+    pub fn merge(&self, other: &BoundingBox) -> BoundingBox {
+        let mut result = *self;
+        result.add_point(&other.min);
+        result.add_point(&other.max);
+        result
+    }
+
+    pub fn transform(&self, m: &Matrix4) -> BoundingBox {
+        // Corners of an unbounded box are not well defined, so it
+        // stays unbounded under every transformation.
+        if !self.is_finite() {
+            return BoundingBox::infinite();
+        }
+
+        let mut result = BoundingBox::empty();
+        for &x in [self.min.x, self.max.x].iter() {
+            for &y in [self.min.y, self.max.y].iter() {
+                for &z in [self.min.z, self.max.z].iter() {
+                    result.add_point(&(*m * Tuple::point(x, y, z)));
+                }
+            }
+        }
+        result
+    }
+
+    pub fn centroid(&self) -> Tuple {
+        Tuple::point(
+            (self.min.x + self.max.x) / 2.0,
+            (self.min.y + self.max.y) / 2.0,
+            (self.min.z + self.max.z) / 2.0,
+        )
+    }
+
+    pub fn intersects(&self, r: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.range(r, t_min, t_max).is_some()
+    }
+
+    // Range of t within [t_min, t_max] for which the ray is inside the
+    // box, if any.
+    pub fn range(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let (xtmin, xtmax) = check_axis(r.origin.x, r.direction.x, self.min.x, self.max.x);
+        let (ytmin, ytmax) = check_axis(r.origin.y, r.direction.y, self.min.y, self.max.y);
+        let (ztmin, ztmax) = check_axis(r.origin.z, r.direction.z, self.min.z, self.max.z);
+
+        let tmin = xtmin.max(ytmin).max(ztmin).max(t_min);
+        let tmax = xtmax.min(ytmax).min(ztmax).min(t_max);
+
+        // Be conservative, shapes computing their intersections in
+        // object space can round differently near the box surface.
+        if tmin <= tmax + EPSILON {
+            Some((tmin, tmax))
+        } else {
+            None
+        }
+    }
+}
+
+// Range of t for which the ray is between min and max along a single
+// axis.
+pub fn check_axis(origin: f64, direction: f64, min: f64, max: f64) -> (f64, f64) {
+    // A ray parallel to the slab either lies within it for all t or
+    // never enters it.
+    if direction == 0.0 {
+        if origin < min || origin > max {
+            return (f64::INFINITY, f64::NEG_INFINITY);
+        }
+        return (f64::NEG_INFINITY, f64::INFINITY);
+    }
+
+    let tmin = (min - origin) / direction;
+    let tmax = (max - origin) / direction;
+    if tmin > tmax {
+        return (tmax, tmin);
+    }
+
+    (tmin, tmax)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Material;
+    use crate::shapes::shape::Shape;
+    use crate::shapes::{cylinder::Cylinder, plane::Plane, sphere::Sphere};
+
+    #[test]
+    fn test_intersects() {
+        let b = BoundingBox::new(Tuple::point(-1.0, -1.0, -1.0), Tuple::point(1.0, 1.0, 1.0));
+
+        let r = Ray::new(0.0, 0.0, -5.0, 0.0, 0.0, 1.0);
+        assert!(b.intersects(&r, f64::NEG_INFINITY, f64::INFINITY));
+        assert!(!b.intersects(&r, 0.0, 3.0));
+
+        let r = Ray::new(2.0, 0.0, -5.0, 0.0, 0.0, 1.0);
+        assert!(!b.intersects(&r, f64::NEG_INFINITY, f64::INFINITY));
+
+        let r = Ray::new(0.0, 0.0, 5.0, 0.0, 0.0, 1.0);
+        assert!(b.intersects(&r, f64::NEG_INFINITY, f64::INFINITY));
+        assert!(!b.intersects(&r, 0.0, f64::INFINITY));
+    }
+
+    #[test]
+    fn test_merge() {
+        let a = BoundingBox::new(Tuple::point(-1.0, 0.0, 0.0), Tuple::point(0.0, 1.0, 1.0));
+        let b = BoundingBox::new(Tuple::point(0.0, -2.0, 0.0), Tuple::point(3.0, 0.0, 1.0));
+        let m = a.merge(&b);
+        assert_eq!(m.min, Tuple::point(-1.0, -2.0, 0.0));
+        assert_eq!(m.max, Tuple::point(3.0, 1.0, 1.0));
+
+        let e = BoundingBox::empty().merge(&a);
+        assert_eq!(e.min, a.min);
+        assert_eq!(e.max, a.max);
+        assert!(!BoundingBox::infinite().is_finite());
+    }
+
+    #[test]
+    fn test_shape_bounds() {
+        let s = Sphere::new(
+            Matrix4::translation(1.0, 2.0, 3.0) * Matrix4::scaling(2.0, 1.0, 1.0),
+            Material::default(),
+        );
+        let b = s.bounds();
+        assert_eq!(b.min, Tuple::point(-1.0, 1.0, 2.0));
+        assert_eq!(b.max, Tuple::point(3.0, 3.0, 4.0));
+
+        let p = Plane::new(Matrix4::translation(0.0, -1.0, 0.0), Material::default());
+        assert!(!p.bounds().is_finite());
+
+        let c = Cylinder::new(Matrix4::identity(), Material::default(), -2.0, 3.0, false);
+        let b = c.bounds();
+        assert_eq!(b.min, Tuple::point(-1.0, -2.0, -1.0));
+        assert_eq!(b.max, Tuple::point(1.0, 3.0, 1.0));
+
+        let c = Cylinder::new(Matrix4::identity(), Material::default(), f64::NEG_INFINITY, 3.0, false);
+        assert!(!c.bounds().is_finite());
+    }
+
+    #[test]
+    fn test_rotated_bounds() {
+        let b = BoundingBox::new(Tuple::point(-1.0, -1.0, -1.0), Tuple::point(1.0, 1.0, 1.0))
+            .transform(&Matrix4::rotation_y(45.0));
+        let r = 2.0_f64.sqrt();
+        assert_eq!(b.min, Tuple::point(-r, -1.0, -r));
+        assert_eq!(b.max, Tuple::point(r, 1.0, r));
+    }
+
+    #[test]
+    fn test_check_axis() {
+        assert_eq!(check_axis(0.0, 1.0, -1.0, 1.0), (-1.0, 1.0));
+        assert_eq!(check_axis(5.0, -2.0, -1.0, 1.0), (2.0, 3.0));
+        assert_eq!(check_axis(0.0, 0.0, -1.0, 1.0), (f64::NEG_INFINITY, f64::INFINITY));
+        assert_eq!(check_axis(2.0, 0.0, -1.0, 1.0), (f64::INFINITY, f64::NEG_INFINITY));
+    }
+}
@@ -0,0 +1,271 @@
+use crate::linalg::matrix4::Matrix4;
+use crate::linalg::tuple::Tuple;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::shapes::bounds::BoundingBox;
+use crate::shapes::shape::Shape;
+use crate::utils::EPSILON;
+use crate::world::Intersection;
+
+use uuid::Uuid;
+
+
+// Double-napped cone around the y axis whose radius at height y is |y|,
+// truncated at min_y and max_y.
+#[derive(Clone)]
+pub struct Cone {
+    pub id: Uuid,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
+    pub material: Material,
+
+    pub min_y: f64,
+    pub max_y: f64,
+    pub closed: bool,
+}
+
+impl Cone {
+    #[allow(dead_code)]
+    pub fn new(transformation: Matrix4, material: Material, min_y: f64, max_y: f64, closed: bool) -> Cone {
+        Cone {
+            id: Uuid::new_v4(),
+            inv_transformation: transformation.invert(),
+            transformation: transformation,
+            material: material,
+            min_y: min_y,
+            max_y: max_y,
+            closed: closed,
+        }
+    }
+
+    pub fn default() -> Cone {
+        Cone {
+            id: Uuid::new_v4(),
+            inv_transformation: Matrix4::identity(),
+            transformation: Matrix4::identity(),
+            material: Material::default(),
+            min_y: 0.0,
+            max_y: 1.0,
+            closed: false,
+        }
+    }
+
+    pub fn check_cap(&self, r: &Ray, t: f64, radius: f64) -> bool {
+        let x = r.origin.x + t * r.direction.x;
+        let z = r.origin.z + t * r.direction.z;
+        (x * x + z * z) <= radius * radius
+    }
+
+    pub fn intersect_caps(&self, r: &Ray) -> Vec<Intersection> {
+        let mut result = Vec::new();
+        if self.closed && r.direction.y.abs() > EPSILON {
+            let t1 = (self.min_y - r.origin.y) / r.direction.y;
+            if self.check_cap(r, t1, self.min_y.abs()) {
+                result.push(Intersection::new(t1, self));
+            }
+
+            let t2 = (self.max_y - r.origin.y) / r.direction.y;
+            if self.check_cap(r, t2, self.max_y.abs()) {
+                result.push(Intersection::new(t2, self));
+            }
+        }
+        result
+    }
+
+    fn on_cap(&self, p: &Tuple) -> Option<f64> {
+        let dist = (p.x * p.x) + (p.z * p.z);
+        if self.closed && dist < self.max_y * self.max_y && p.y >= self.max_y - EPSILON {
+            return Some(1.0);
+        }
+        if self.closed && dist < self.min_y * self.min_y && p.y <= self.min_y + EPSILON {
+            return Some(-1.0);
+        }
+        None
+    }
+}
+
+impl Shape for Cone {
+    fn normal_at(&self, p: &Tuple) -> Tuple {
+        let op = self.inv_transformation * *p;
+
+        let on = match self.on_cap(&op) {
+            Some(y) => Tuple::vector(0.0, y, 0.0),
+            None => {
+                let dist = (op.x * op.x) + (op.z * op.z);
+                let mut y = dist.sqrt();
+                if op.y > 0.0 {
+                    y = -y;
+                }
+                Tuple::vector(op.x, y, op.z)
+            }
+        };
+
+        let mut wn = self.inv_transformation.transpose() * on;
+        wn.w = 0.0;
+        wn.normalize()
+    }
+
+    fn intersect(&self, r: &Ray) -> Vec<Intersection> {
+        let mut result = Vec::new();
+
+        let ray = r.transform(&self.inv_transformation);
+        let (o, d) = (ray.origin, ray.direction);
+        let a = (d.x * d.x) - (d.y * d.y) + (d.z * d.z);
+        let b = (2.0 * o.x * d.x) - (2.0 * o.y * d.y) + (2.0 * o.z * d.z);
+        let c = (o.x * o.x) - (o.y * o.y) + (o.z * o.z);
+
+        if a.abs() > EPSILON {
+            let discriminant = (b * b) - (4.0 * a * c);
+
+            if discriminant >= 0.0 {
+                let t1 = (-b - discriminant.sqrt()) / (2.0 * a);
+                let y1 = o.y + t1 * d.y;
+                if self.min_y <= y1 && y1 <= self.max_y {
+                    result.push(Intersection::new(t1, self));
+                }
+
+                let t2 = (-b + discriminant.sqrt()) / (2.0 * a);
+                let y2 = o.y + t2 * d.y;
+                if self.min_y <= y2 && y2 <= self.max_y {
+                    result.push(Intersection::new(t2, self));
+                }
+            }
+        } else if b.abs() > EPSILON {
+            // Ray is parallel to one of the halves and hits the other
+            // one once.
+            let t = -c / (2.0 * b);
+            let y = o.y + t * d.y;
+            if self.min_y <= y && y <= self.max_y {
+                result.push(Intersection::new(t, self));
+            }
+        }
+
+        let mut caps = self.intersect_caps(&ray);
+        result.append(&mut caps);
+
+        result
+    }
+
+    fn uv_coordinates(&self, p: &Tuple) -> Tuple {
+        if self.on_cap(p).is_some() {
+            let u = p.x.rem_euclid(1.0);
+            let v = p.z.rem_euclid(1.0);
+            Tuple::point(u, v, 0.0)
+        } else {
+            let theta = p.x.atan2(p.z);
+            let raw_u = theta / (2.0 * std::f64::consts::PI);
+            let u = 1.0 - (raw_u + 0.5);
+            let v = p.y.rem_euclid(1.0);
+            Tuple::point(u, v, 0.0)
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let limit = self.min_y.abs().max(self.max_y.abs());
+        BoundingBox::new(Tuple::point(-limit, self.min_y, -limit), Tuple::point(limit, self.max_y, limit))
+            .transform(&self.transformation)
+    }
+
+    fn get_id(&self) -> &Uuid {
+        &self.id
+    }
+
+    fn set_transformation(&mut self, t: Matrix4) {
+        self.inv_transformation = t.invert();
+        self.transformation = t;
+    }
+    fn get_transformation(&self) -> &Matrix4 {
+        &self.transformation
+    }
+    fn get_inverse_transformation(&self) -> &Matrix4 {
+        &self.inv_transformation
+    }
+
+    fn set_material(&mut self, mat: Material) {
+        self.material = mat;
+    }
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::equal;
+
+    fn infinite_cone() -> Cone {
+        Cone::new(Matrix4::identity(), Material::default(), f64::NEG_INFINITY, f64::INFINITY, false)
+    }
+
+    #[test]
+    fn test_intersect() {
+        let c = infinite_cone();
+        let cases = vec![
+            (Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0), 5.0, 5.0),
+            (Tuple::point(0.0, 0.0, -5.0), Tuple::vector(1.0, 1.0, 1.0), 8.66025, 8.66025),
+            (Tuple::point(1.0, 1.0, -5.0), Tuple::vector(-0.5, -1.0, 1.0), 4.55006, 49.44994),
+        ];
+        for (origin, direction, t1, t2) in cases.into_iter() {
+            let r = Ray { origin: origin, direction: direction.normalize(), time: 0.0 };
+            let xs = c.intersect(&r);
+            assert_eq!(xs.len(), 2);
+            assert!(equal(xs[0].t, t1));
+            assert!(equal(xs[1].t, t2));
+        }
+    }
+
+    #[test]
+    fn test_intersect_parallel() {
+        let c = infinite_cone();
+        let r = Ray {
+            origin: Tuple::point(0.0, 0.0, -1.0),
+            direction: Tuple::vector(0.0, 1.0, 1.0).normalize(),
+            time: 0.0,
+        };
+        let xs = c.intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert!(equal(xs[0].t, 0.35355));
+    }
+
+    #[test]
+    fn test_intersect_caps() {
+        let c = Cone::new(Matrix4::identity(), Material::default(), -0.5, 0.5, true);
+        let cases = vec![
+            (Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 1.0, 0.0), 0),
+            (Tuple::point(0.0, 0.0, -0.25), Tuple::vector(0.0, 1.0, 1.0), 2),
+            (Tuple::point(0.0, 0.0, -0.25), Tuple::vector(0.0, 1.0, 0.0), 4),
+        ];
+        for (origin, direction, count) in cases.into_iter() {
+            let r = Ray { origin: origin, direction: direction.normalize(), time: 0.0 };
+            assert_eq!(c.intersect(&r).len(), count);
+        }
+    }
+
+    #[test]
+    fn test_normal_at() {
+        let c = infinite_cone();
+        assert_eq!(
+            c.normal_at(&Tuple::point(1.0, 1.0, 1.0)),
+            Tuple::vector(1.0, -2.0_f64.sqrt(), 1.0).normalize()
+        );
+        assert_eq!(
+            c.normal_at(&Tuple::point(-1.0, -1.0, 0.0)),
+            Tuple::vector(-1.0, 1.0, 0.0).normalize()
+        );
+
+        let c = Cone::new(Matrix4::identity(), Material::default(), -0.5, 0.5, true);
+        assert_eq!(c.normal_at(&Tuple::point(0.1, 0.5, 0.1)), Tuple::vector(0.0, 1.0, 0.0));
+        assert_eq!(c.normal_at(&Tuple::point(0.1, -0.5, 0.0)), Tuple::vector(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let c = Cone::new(Matrix4::identity(), Material::default(), -2.0, 1.0, true);
+        let b = c.bounds();
+        assert_eq!(b.min, Tuple::point(-2.0, -2.0, -2.0));
+        assert_eq!(b.max, Tuple::point(2.0, 1.0, 2.0));
+        assert!(!infinite_cone().bounds().is_finite());
+    }
+}
@@ -0,0 +1,277 @@
+use crate::linalg::matrix4::Matrix4;
+use crate::linalg::tuple::Tuple;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::shapes::bounds::BoundingBox;
+use crate::shapes::shape::Shape;
+use crate::world::Intersection;
+
+use uuid::Uuid;
+
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl CsgOperation {
+    pub fn from_name(name: &str) -> CsgOperation {
+        match name {
+            "union" => CsgOperation::Union,
+            "intersection" => CsgOperation::Intersection,
+            "difference" => CsgOperation::Difference,
+            _ => panic!("Undefined CSG operation: {:?}!", name),
+        }
+    }
+
+    // Whether a hit on the left (or right) child is part of the
+    // combined surface, given whether the ray is currently inside the
+    // left and right child.
+    pub fn intersection_allowed(&self, left_hit: bool, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => (left_hit && !in_right) || (!left_hit && !in_left),
+            CsgOperation::Intersection => (left_hit && in_right) || (!left_hit && in_left),
+            CsgOperation::Difference => (left_hit && !in_right) || (!left_hit && in_left),
+        }
+    }
+}
+
+
+// Constructive solid geometry combining two children. Like a group it
+// pushes its transformation down to the children. Intersections on the
+// combined surface keep the child as their object, so normals and
+// materials come from the child that was hit, but use the CSG shape as
+// their container, so refraction treats the combined shape as a single
+// volume made of the left child's material.
+pub struct Csg {
+    pub id: Uuid,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
+
+    pub operation: CsgOperation,
+    pub left: Box<dyn Shape + Sync>,
+    pub right: Box<dyn Shape + Sync>,
+    pub left_transformation: Matrix4,
+    pub right_transformation: Matrix4,
+}
+
+impl Csg {
+    pub fn new(
+        operation: CsgOperation,
+        left: Box<dyn Shape + Sync>,
+        right: Box<dyn Shape + Sync>,
+        transformation: Matrix4
+    ) -> Csg {
+        let mut result = Csg {
+            id: Uuid::new_v4(),
+            transformation: Matrix4::identity(),
+            inv_transformation: Matrix4::identity(),
+            operation: operation,
+            left_transformation: *left.get_transformation(),
+            right_transformation: *right.get_transformation(),
+            left: left,
+            right: right,
+        };
+        result.set_transformation(transformation);
+        result
+    }
+
+    pub fn filter_intersections<'a>(&self, intersections: Vec<Intersection<'a>>) -> Vec<Intersection<'a>> {
+        let mut in_left = false;
+        let mut in_right = false;
+
+        let mut result = Vec::new();
+        for i in intersections.into_iter() {
+            let left_hit = self.left.includes(i.object.get_id());
+
+            if self.operation.intersection_allowed(left_hit, in_left, in_right) {
+                result.push(i);
+            }
+
+            if left_hit {
+                in_left = !in_left;
+            } else {
+                in_right = !in_right;
+            }
+        }
+        result
+    }
+}
+
+impl Shape for Csg {
+    fn normal_at(&self, _p: &Tuple) -> Tuple {
+        panic!("Calling normal_at() on a CSG shape, intersections refer to its children!");
+    }
+
+    fn intersect(&self, r: &Ray) -> Vec<Intersection> {
+        let b = self.bounds();
+        if b.is_finite() && !b.intersects(r, f64::NEG_INFINITY, f64::INFINITY) {
+            return Vec::new();
+        }
+
+        let mut intersections = self.left.intersect(r);
+        intersections.append(&mut self.right.intersect(r));
+        intersections.sort_by(|a, b| a.t.partial_cmp(&b.t).unwrap());
+
+        let mut result = self.filter_intersections(intersections);
+        for i in result.iter_mut() {
+            i.container = self;
+        }
+        result
+    }
+
+    fn uv_coordinates(&self, _p: &Tuple) -> Tuple {
+        panic!("Calling uv_coordinates() on a CSG shape, intersections refer to its children!");
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        // Intersections and differences never extend beyond the left
+        // child.
+        match self.operation {
+            CsgOperation::Union => self.left.bounds().merge(&self.right.bounds()),
+            _ => self.left.bounds(),
+        }
+    }
+
+    fn includes(&self, id: &Uuid) -> bool {
+        &self.id == id || self.left.includes(id) || self.right.includes(id)
+    }
+
+    fn get_id(&self) -> &Uuid {
+        &self.id
+    }
+
+    fn set_transformation(&mut self, t: Matrix4) {
+        self.inv_transformation = t.invert();
+        self.transformation = t;
+        self.left.set_transformation(self.transformation * self.left_transformation);
+        self.right.set_transformation(self.transformation * self.right_transformation);
+    }
+    fn get_transformation(&self) -> &Matrix4 {
+        &self.transformation
+    }
+
+    fn get_inverse_transformation(&self) -> &Matrix4 {
+        &self.inv_transformation
+    }
+
+    fn set_material(&mut self, mat: Material) {
+        self.left.set_material(mat.clone());
+        self.right.set_material(mat);
+    }
+    fn get_material(&self) -> &Material {
+        self.left.get_material()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::{cube::Cube, sphere::Sphere};
+    use crate::world::World;
+
+    #[test]
+    fn test_intersection_allowed() {
+        let cases = vec![
+            (CsgOperation::Union, true, true, true, false),
+            (CsgOperation::Union, true, true, false, true),
+            (CsgOperation::Union, true, false, true, false),
+            (CsgOperation::Union, true, false, false, true),
+            (CsgOperation::Union, false, true, true, false),
+            (CsgOperation::Union, false, true, false, false),
+            (CsgOperation::Union, false, false, true, true),
+            (CsgOperation::Union, false, false, false, true),
+            (CsgOperation::Intersection, true, true, true, true),
+            (CsgOperation::Intersection, true, true, false, false),
+            (CsgOperation::Intersection, true, false, true, true),
+            (CsgOperation::Intersection, true, false, false, false),
+            (CsgOperation::Intersection, false, true, true, true),
+            (CsgOperation::Intersection, false, true, false, true),
+            (CsgOperation::Intersection, false, false, true, false),
+            (CsgOperation::Intersection, false, false, false, false),
+            (CsgOperation::Difference, true, true, true, false),
+            (CsgOperation::Difference, true, true, false, true),
+            (CsgOperation::Difference, true, false, true, false),
+            (CsgOperation::Difference, true, false, false, true),
+            (CsgOperation::Difference, false, true, true, true),
+            (CsgOperation::Difference, false, true, false, true),
+            (CsgOperation::Difference, false, false, true, false),
+            (CsgOperation::Difference, false, false, false, false),
+        ];
+        for (op, lhit, inl, inr, expected) in cases.into_iter() {
+            assert_eq!(op.intersection_allowed(lhit, inl, inr), expected);
+        }
+    }
+
+    #[test]
+    fn test_filter_intersections() {
+        let cases = vec![
+            (CsgOperation::Union, vec![1.0, 6.0]),
+            (CsgOperation::Intersection, vec![2.0, 4.0]),
+            (CsgOperation::Difference, vec![1.0, 2.0]),
+        ];
+        for (op, expected) in cases.into_iter() {
+            let s1 = Sphere::default();
+            let s2 = Cube::default();
+            let c = Csg::new(op, Box::new(s1), Box::new(s2), Matrix4::identity());
+
+            let xs = vec![
+                Intersection::new(1.0, c.left.as_ref()),
+                Intersection::new(2.0, c.right.as_ref()),
+                Intersection::new(4.0, c.left.as_ref()),
+                Intersection::new(6.0, c.right.as_ref()),
+            ];
+            let ts: Vec<f64> = c.filter_intersections(xs).iter().map(|i| i.t).collect();
+            assert_eq!(ts, expected);
+        }
+    }
+
+    #[test]
+    fn test_intersect() {
+        let c = Csg::new(
+            CsgOperation::Union,
+            Box::new(Sphere::default()),
+            Box::new(Cube::default()),
+            Matrix4::identity(),
+        );
+        let r = Ray::new(0.0, 2.0, -5.0, 0.0, 0.0, 1.0);
+        assert_eq!(c.intersect(&r).len(), 0);
+
+        let s1 = Sphere::default();
+        let s2 = Sphere::new(Matrix4::translation(0.0, 0.0, 0.5), Material::default());
+        let c = Csg::new(CsgOperation::Union, Box::new(s1), Box::new(s2), Matrix4::identity());
+        let r = Ray::new(0.0, 0.0, -5.0, 0.0, 0.0, 1.0);
+        let xs = c.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 4.0);
+        assert!(c.left.includes(xs[0].object.get_id()));
+        assert_eq!(xs[1].t, 6.5);
+        assert!(c.right.includes(xs[1].object.get_id()));
+    }
+
+    #[test]
+    fn test_difference_refraction() {
+        // Glass cube with a spherical hole: the walls of the hole are
+        // where the ray leaves and re-enters the glass.
+        let mut glass = Material::default();
+        glass.transparency = 1.0;
+        glass.refractive_index = 1.5;
+        let cube = Cube::new(Matrix4::scaling(2.0, 2.0, 2.0), glass.clone());
+        let hole = Sphere::new(Matrix4::identity(), glass);
+        let c = Csg::new(CsgOperation::Difference, Box::new(cube), Box::new(hole), Matrix4::identity());
+
+        let world = World::new(vec![Box::new(c)], World::default().lights);
+        let r = Ray::new(0.0, 0.0, -5.0, 0.0, 0.0, 1.0);
+        let xs = world.intersect(&r);
+        let n: Vec<Vec<f64>> = xs.iter().map(|i| vec![i.t, i.n1, i.n2]).collect();
+        assert_eq!(n, vec![
+            vec![3.0, 1.0, 1.5],
+            vec![4.0, 1.5, 1.0],
+            vec![6.0, 1.0, 1.5],
+            vec![7.0, 1.5, 1.0],
+        ]);
+    }
+}
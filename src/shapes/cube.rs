@@ -1,10 +1,11 @@
 use crate::color::Color;
-use crate::linalg::matrix::Matrix;
+use crate::linalg::matrix4::Matrix4;
 use crate::linalg::tuple::Tuple;
 use crate::material::Material;
 use crate::ray::Ray;
+use crate::shapes::bounds::{self, BoundingBox};
 use crate::shapes::shape::Shape;
-use crate::utils::{EPSILON, equal};
+use crate::utils::equal;
 use crate::world::Intersection;
 
 use uuid::Uuid;
@@ -13,14 +14,14 @@ use uuid::Uuid;
 #[derive(Clone)]
 pub struct Cube {
     pub id: Uuid,
-    pub transformation: Matrix,
-    pub inv_transformation: Matrix,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
     pub material: Material,
 }
 
 impl Cube {
     #[allow(dead_code)]
-    pub fn new(transformation: Matrix, material: Material) -> Cube {
+    pub fn new(transformation: Matrix4, material: Material) -> Cube {
         Cube {
             id: Uuid::new_v4(),
             inv_transformation: transformation.invert(),
@@ -32,8 +33,8 @@ impl Cube {
     pub fn default() -> Cube {
         Cube {
             id: Uuid::new_v4(),
-            transformation: Matrix::identity(4),
-            inv_transformation: Matrix::identity(4),
+            transformation: Matrix4::identity(),
+            inv_transformation: Matrix4::identity(),
             material: Material::new(
                 Some(Color::new(0.0, 0.0, 1.0)),
                 None,
@@ -43,29 +44,14 @@ impl Cube {
     }
 
     pub fn check_axis(&self, origin: f64, direction: f64) -> (f64, f64) {
-        let tmin_numerator = -1.0 - origin;
-        let tmax_numerator = 1.0 - origin;
-
-        let mut tmin = tmin_numerator * f64::INFINITY;
-        let mut tmax = tmax_numerator * f64::INFINITY;
-        if direction.abs() > EPSILON {
-            tmin = tmin_numerator / direction;
-            tmax = tmax_numerator / direction;
-        }
-
-        if tmin > tmax {
-            return (tmax, tmin);
-        }
-
-        (tmin, tmax)
+        bounds::check_axis(origin, direction, -1.0, 1.0)
     }
 
-
 }
 
 impl Shape for Cube {
     fn normal_at(&self, p: &Tuple) -> Tuple {
-        let op = self.inv_transformation.multiply_tuple(p);
+        let op = self.inv_transformation * *p;
 
         let maxc = op.x.abs().max(op.y.abs()).max(op.z.abs());
 
@@ -104,19 +90,24 @@ impl Shape for Cube {
         Tuple::point(p.x, p.y, p.z)
     }
 
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(Tuple::point(-1.0, -1.0, -1.0), Tuple::point(1.0, 1.0, 1.0))
+            .transform(&self.transformation)
+    }
+
     fn get_id(&self) -> &Uuid {
         &self.id
     }
 
-    fn set_transformation(&mut self, t: Matrix) {
+    fn set_transformation(&mut self, t: Matrix4) {
         self.inv_transformation = t.invert();
         self.transformation = t;
     }
-    fn get_transformation(&self) -> &Matrix {
+    fn get_transformation(&self) -> &Matrix4 {
         &self.transformation
     }
 
-    fn get_inverse_transformation(&self) -> &Matrix {
+    fn get_inverse_transformation(&self) -> &Matrix4 {
         &self.inv_transformation
     }
 
@@ -1,7 +1,8 @@
-use crate::linalg::matrix::Matrix;
+use crate::linalg::matrix4::Matrix4;
 use crate::linalg::tuple::Tuple;
 use crate::material::Material;
 use crate::ray::Ray;
+use crate::shapes::bounds::BoundingBox;
 use crate::shapes::shape::Shape;
 use crate::utils::EPSILON;
 use crate::world::Intersection;
@@ -12,8 +13,8 @@ use uuid::Uuid;
 #[derive(Clone)]
 pub struct Cylinder {
     pub id: Uuid,
-    pub transformation: Matrix,
-    pub inv_transformation: Matrix,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
     pub material: Material,
 
     pub min_y: f64,
@@ -23,7 +24,7 @@ pub struct Cylinder {
 
 impl Cylinder {
     #[allow(dead_code)]
-    pub fn new(transformation: Matrix, material: Material, min_y: f64, max_y: f64, closed: bool) -> Cylinder {
+    pub fn new(transformation: Matrix4, material: Material, min_y: f64, max_y: f64, closed: bool) -> Cylinder {
         Cylinder {
             id: Uuid::new_v4(),
             inv_transformation: transformation.invert(),
@@ -38,8 +39,8 @@ impl Cylinder {
     pub fn default() -> Cylinder {
         Cylinder {
             id: Uuid::new_v4(),
-            inv_transformation: Matrix::identity(4),
-            transformation: Matrix::identity(4),
+            inv_transformation: Matrix4::identity(),
+            transformation: Matrix4::identity(),
             material: Material::default(),
             min_y: 0.0,
             max_y: 1.0,
@@ -72,18 +73,16 @@ impl Cylinder {
 
 impl Shape for Cylinder {
     fn normal_at(&self, p: &Tuple) -> Tuple {
-        let op = self.inv_transformation.multiply_tuple(p);
+        let op = self.inv_transformation * *p;
 
         let dist = (op.x * op.x) + (op.z * op.z);
 
         let mut result = Tuple::vector(op.x, 0.0, op.z);
         if dist < 1.0 && op.y >= self.max_y - EPSILON {
-            result = self.inv_transformation
-                .multiply_tuple(&Tuple::vector(0.0, 1.0, 0.0));
+            result = self.inv_transformation * Tuple::vector(0.0, 1.0, 0.0);
         }
         if dist < 1.0 && op.y <= self.min_y + EPSILON {
-            result = self.inv_transformation
-                .multiply_tuple(&Tuple::vector(0.0, -1.0, 0.0));
+            result = self.inv_transformation * Tuple::vector(0.0, -1.0, 0.0);
         }
 
         result.normalize()
@@ -137,18 +136,23 @@ impl Shape for Cylinder {
         }
     }
 
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(Tuple::point(-1.0, self.min_y, -1.0), Tuple::point(1.0, self.max_y, 1.0))
+            .transform(&self.transformation)
+    }
+
     fn get_id(&self) -> &Uuid {
         &self.id
     }
 
-    fn set_transformation(&mut self, t: Matrix) {
+    fn set_transformation(&mut self, t: Matrix4) {
         self.inv_transformation = t.invert();
         self.transformation = t;
     }
-    fn get_transformation(&self) -> &Matrix {
+    fn get_transformation(&self) -> &Matrix4 {
         &self.transformation
     }
-    fn get_inverse_transformation(&self) -> &Matrix {
+    fn get_inverse_transformation(&self) -> &Matrix4 {
         &self.inv_transformation
     }
 
@@ -0,0 +1,160 @@
+use crate::linalg::matrix4::Matrix4;
+use crate::linalg::tuple::Tuple;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::shapes::bounds::BoundingBox;
+use crate::shapes::shape::Shape;
+use crate::utils::EPSILON;
+use crate::world::Intersection;
+
+use uuid::Uuid;
+
+
+// Flat disk in the xz plane centered at the origin. A positive
+// inner_radius cuts a hole into it, turning it into an annulus.
+#[derive(Clone)]
+pub struct Disk {
+    pub id: Uuid,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
+    pub material: Material,
+
+    pub radius: f64,
+    pub inner_radius: f64,
+}
+
+impl Disk {
+    #[allow(dead_code)]
+    pub fn new(transformation: Matrix4, material: Material, radius: f64, inner_radius: f64) -> Disk {
+        Disk {
+            id: Uuid::new_v4(),
+            inv_transformation: transformation.invert(),
+            transformation: transformation,
+            material: material,
+            radius: radius,
+            inner_radius: inner_radius,
+        }
+    }
+
+    pub fn default() -> Disk {
+        Disk {
+            id: Uuid::new_v4(),
+            inv_transformation: Matrix4::identity(),
+            transformation: Matrix4::identity(),
+            material: Material::default(),
+            radius: 1.0,
+            inner_radius: 0.0,
+        }
+    }
+}
+
+impl Shape for Disk {
+    fn normal_at(&self, _p: &Tuple) -> Tuple {
+        let on = Tuple::vector(0.0, 1.0, 0.0);
+        let mut wn = self.inv_transformation.transpose() * on;
+        wn.w = 0.0;
+        wn.normalize()
+    }
+
+    fn intersect(&self, r: &Ray) -> Vec<Intersection> {
+        let mut result = Vec::new();
+
+        let ray = r.transform(&self.inv_transformation);
+        if ray.direction.y.abs() > EPSILON {
+            let t = -ray.origin.y / ray.direction.y;
+            let x = ray.origin.x + t * ray.direction.x;
+            let z = ray.origin.z + t * ray.direction.z;
+            let dist = x * x + z * z;
+            if dist <= self.radius * self.radius && dist >= self.inner_radius * self.inner_radius {
+                result.push(Intersection::new(t, self));
+            }
+        }
+
+        result
+    }
+
+    fn uv_coordinates(&self, p: &Tuple) -> Tuple {
+        // u goes around the center, v from the inner to the outer edge.
+        let theta = p.x.atan2(p.z);
+        let raw_u = theta / (2.0 * std::f64::consts::PI);
+        let u = 1.0 - (raw_u + 0.5);
+
+        let dist = (p.x * p.x + p.z * p.z).sqrt();
+        let v = (dist - self.inner_radius) / (self.radius - self.inner_radius);
+
+        Tuple::point(u, v.clamp(0.0, 1.0), 0.0)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(Tuple::point(-self.radius, 0.0, -self.radius), Tuple::point(self.radius, 0.0, self.radius))
+            .transform(&self.transformation)
+    }
+
+    fn get_id(&self) -> &Uuid {
+        &self.id
+    }
+
+    fn set_transformation(&mut self, t: Matrix4) {
+        self.inv_transformation = t.invert();
+        self.transformation = t;
+    }
+    fn get_transformation(&self) -> &Matrix4 {
+        &self.transformation
+    }
+    fn get_inverse_transformation(&self) -> &Matrix4 {
+        &self.inv_transformation
+    }
+
+    fn set_material(&mut self, mat: Material) {
+        self.material = mat;
+    }
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intersect() {
+        let d = Disk::new(Matrix4::identity(), Material::default(), 1.0, 0.5);
+        let cases = vec![
+            (Ray::new(0.0, 1.0, 0.0, 0.0, -1.0, 0.0), 0),
+            (Ray::new(0.75, 1.0, 0.0, 0.0, -1.0, 0.0), 1),
+            (Ray::new(0.0, 1.0, -0.99, 0.0, -1.0, 0.0), 1),
+            (Ray::new(1.0, 1.0, 1.0, 0.0, -1.0, 0.0), 0),
+            (Ray::new(0.75, 1.0, 0.0, 1.0, 0.0, 0.0), 0),
+        ];
+        for (r, count) in cases.into_iter() {
+            assert_eq!(d.intersect(&r).len(), count);
+        }
+
+        let xs = d.intersect(&Ray::new(0.75, 2.0, 0.0, 0.0, -1.0, 0.0));
+        assert_eq!(xs[0].t, 2.0);
+    }
+
+    #[test]
+    fn test_normal_at() {
+        let d = Disk::new(Matrix4::rotation_x(90.0), Material::default(), 1.0, 0.0);
+        assert_eq!(d.normal_at(&Tuple::point(0.5, 0.0, 0.0)), Tuple::vector(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_uv_coordinates() {
+        let d = Disk::new(Matrix4::identity(), Material::default(), 2.0, 1.0);
+        assert_eq!(d.uv_coordinates(&Tuple::point(0.0, 0.0, -1.0)), Tuple::point(0.0, 0.0, 0.0));
+        assert_eq!(d.uv_coordinates(&Tuple::point(1.5, 0.0, 0.0)), Tuple::point(0.25, 0.5, 0.0));
+        assert_eq!(d.uv_coordinates(&Tuple::point(0.0, 0.0, 2.0)), Tuple::point(0.5, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let d = Disk::new(Matrix4::translation(0.0, 1.0, 0.0), Material::default(), 2.0, 0.0);
+        let b = d.bounds();
+        assert_eq!(b.min, Tuple::point(-2.0, 1.0, -2.0));
+        assert_eq!(b.max, Tuple::point(2.0, 1.0, 2.0));
+    }
+}
@@ -0,0 +1,175 @@
+use crate::bvh::Bvh;
+use crate::linalg::matrix4::Matrix4;
+use crate::linalg::tuple::Tuple;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::shapes::bounds::BoundingBox;
+use crate::shapes::shape::Shape;
+use crate::world::Intersection;
+
+use uuid::Uuid;
+
+
+// A group owns its children and keeps their transformations in sync
+// with its own: every child is transformed by the group transformation
+// followed by the transformation it had when it was added. Children
+// therefore always know their full world transformation, which is all
+// that is needed to compute normals and pattern positions through any
+// number of nested groups.
+pub struct Group {
+    pub id: Uuid,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
+    pub material: Material,
+
+    pub children: Vec<Box<dyn Shape + Sync>>,
+    pub child_transformations: Vec<Matrix4>,
+    pub bvh: Bvh,
+    pub bounds: BoundingBox,
+}
+
+impl Group {
+    pub fn new(transformation: Matrix4, children: Vec<Box<dyn Shape + Sync>>) -> Group {
+        // Children get their world transformations and the hierarchy is
+        // built once, in set_transformation, not for every child.
+        let mut result = Group::default();
+        for child in children.into_iter() {
+            result.child_transformations.push(*child.get_transformation());
+            result.children.push(child);
+        }
+        result.set_transformation(transformation);
+        result
+    }
+
+    pub fn default() -> Group {
+        Group {
+            id: Uuid::new_v4(),
+            transformation: Matrix4::identity(),
+            inv_transformation: Matrix4::identity(),
+            material: Material::default(),
+            children: vec![],
+            child_transformations: vec![],
+            bvh: Bvh::new(&[]),
+            bounds: BoundingBox::empty(),
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.bvh = Bvh::new(&self.children);
+        self.bounds = BoundingBox::empty();
+        for child in self.children.iter() {
+            self.bounds = self.bounds.merge(&child.bounds());
+        }
+    }
+}
+
+impl Shape for Group {
+    fn normal_at(&self, _p: &Tuple) -> Tuple {
+        panic!("Calling normal_at() on a group, intersections refer to its children!");
+    }
+
+    fn intersect(&self, r: &Ray) -> Vec<Intersection> {
+        let mut result = Vec::new();
+
+        for i in self.bvh.candidates(r, f64::NEG_INFINITY, f64::INFINITY) {
+            let mut intersections = self.children[i].intersect(r);
+            result.append(&mut intersections);
+        }
+
+        result
+    }
+
+    fn uv_coordinates(&self, _p: &Tuple) -> Tuple {
+        panic!("Calling uv_coordinates() on a group, intersections refer to its children!");
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+
+    fn includes(&self, id: &Uuid) -> bool {
+        &self.id == id || self.children.iter().any(|c| c.includes(id))
+    }
+
+    fn get_id(&self) -> &Uuid {
+        &self.id
+    }
+
+    fn set_transformation(&mut self, t: Matrix4) {
+        self.inv_transformation = t.invert();
+        self.transformation = t;
+        for (child, local) in self.children.iter_mut().zip(self.child_transformations.iter()) {
+            child.set_transformation(self.transformation * *local);
+        }
+        self.update();
+    }
+    fn get_transformation(&self) -> &Matrix4 {
+        &self.transformation
+    }
+
+    fn get_inverse_transformation(&self) -> &Matrix4 {
+        &self.inv_transformation
+    }
+
+    fn set_material(&mut self, mat: Material) {
+        for child in self.children.iter_mut() {
+            child.set_material(mat.clone());
+        }
+        self.material = mat;
+    }
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::sphere::Sphere;
+
+    #[test]
+    fn test_intersect() {
+        let s1 = Sphere::default();
+        let s2 = Sphere::new(Matrix4::translation(0.0, 0.0, -3.0), Material::default());
+        let s3 = Sphere::new(Matrix4::translation(5.0, 0.0, 0.0), Material::default());
+        let g = Group::new(Matrix4::identity(), vec![Box::new(s1), Box::new(s2), Box::new(s3)]);
+
+        let r = Ray::new(0.0, 0.0, -5.0, 0.0, 0.0, 1.0);
+        let mut ts: Vec<f64> = g.intersect(&r).iter().map(|i| i.t).collect();
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(ts, vec![1.0, 3.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn test_transformed_intersect() {
+        let s = Sphere::new(Matrix4::translation(5.0, 0.0, 0.0), Material::default());
+        let g = Group::new(Matrix4::scaling(2.0, 2.0, 2.0), vec![Box::new(s)]);
+
+        let r = Ray::new(10.0, 0.0, -10.0, 0.0, 0.0, 1.0);
+        assert_eq!(g.intersect(&r).len(), 2);
+    }
+
+    #[test]
+    fn test_nested_normal() {
+        let s = Sphere::new(Matrix4::translation(5.0, 0.0, 0.0), Material::default());
+        let g2 = Group::new(Matrix4::scaling(1.0, 2.0, 3.0), vec![Box::new(s)]);
+        let g1 = Group::new(Matrix4::rotation_y(90.0), vec![Box::new(g2)]);
+
+        let r = Ray::new(1.7321, 1.1547, -10.0, 0.0, 0.0, 1.0);
+        let ix = g1.intersect(&r);
+        assert_eq!(ix.len(), 2);
+
+        let n = ix[0].object.normal_at(&Tuple::point(1.7321, 1.1547, -5.5774));
+        assert_eq!(n, Tuple::vector(0.2857, 0.4286, -0.8571));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let s = Sphere::new(Matrix4::translation(2.0, 0.0, 0.0), Material::default());
+        let g = Group::new(Matrix4::translation(0.0, 1.0, 0.0), vec![Box::new(s)]);
+        let b = g.bounds();
+        assert_eq!(b.min, Tuple::point(1.0, 0.0, -1.0));
+        assert_eq!(b.max, Tuple::point(3.0, 2.0, 1.0));
+    }
+}
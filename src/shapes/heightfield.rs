@@ -0,0 +1,414 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::linalg::matrix4::Matrix4;
+use crate::linalg::tuple::Tuple;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::shapes::bounds::BoundingBox;
+use crate::shapes::shape::Shape;
+use crate::utils::EPSILON;
+use crate::world::Intersection;
+
+use uuid::Uuid;
+
+
+// Terrain given by a grid of heights between 0 and 1. The grid covers
+// x and z from -scale/2 to scale/2 and rises up to scale.y, each cell is
+// split into two triangles. Rays walk the grid cell by cell, so the
+// cost of an intersection grows with the number of cells crossed, not
+// with the size of the grid.
+#[derive(Clone)]
+pub struct Heightfield {
+    pub id: Uuid,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
+    pub material: Material,
+
+    // Number of grid points along x and z, heights are stored row by
+    // row along x.
+    pub width: usize,
+    pub depth: usize,
+    pub heights: Vec<f64>,
+    pub normals: Vec<Tuple>,
+    pub scale: Tuple,
+    pub min_height: f64,
+    pub max_height: f64,
+}
+
+impl Heightfield {
+    pub fn new(transformation: Matrix4, material: Material, width: usize, depth: usize, heights: Vec<f64>, scale: Tuple) -> Heightfield {
+        assert!(width >= 2 && depth >= 2, "Heightfields need at least 2x2 grid points!");
+        assert_eq!(heights.len(), width * depth);
+
+        let mut result = Heightfield {
+            id: Uuid::new_v4(),
+            inv_transformation: transformation.invert(),
+            transformation: transformation,
+            material: material,
+            width: width,
+            depth: depth,
+            min_height: heights.iter().cloned().fold(f64::INFINITY, f64::min),
+            max_height: heights.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            heights: heights,
+            normals: vec![],
+            scale: scale,
+        };
+        result.update_normals();
+        result
+    }
+
+    // Size of a single cell in object space.
+    fn cell_size(&self) -> (f64, f64) {
+        (self.scale.x / (self.width - 1) as f64, self.scale.z / (self.depth - 1) as f64)
+    }
+
+    fn height(&self, i: usize, j: usize) -> f64 {
+        self.heights[j * self.width + i] * self.scale.y
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Tuple {
+        let (dx, dz) = self.cell_size();
+        Tuple::point(
+            i as f64 * dx - self.scale.x / 2.0,
+            self.height(i, j),
+            j as f64 * dz - self.scale.z / 2.0,
+        )
+    }
+
+    // Vertex normals from central differences of the neighbouring
+    // heights.
+    fn update_normals(&mut self) {
+        let (dx, dz) = self.cell_size();
+        let mut normals = Vec::with_capacity(self.heights.len());
+        for j in 0..self.depth {
+            for i in 0..self.width {
+                let (i0, i1) = (i.saturating_sub(1), (i + 1).min(self.width - 1));
+                let (j0, j1) = (j.saturating_sub(1), (j + 1).min(self.depth - 1));
+                let slope_x = (self.height(i1, j) - self.height(i0, j)) / ((i1 - i0) as f64 * dx);
+                let slope_z = (self.height(i, j1) - self.height(i, j0)) / ((j1 - j0) as f64 * dz);
+                normals.push(Tuple::vector(-slope_x, 1.0, -slope_z).normalize());
+            }
+        }
+        self.normals = normals;
+    }
+
+    // Position in grid units, integer values are grid points.
+    fn grid_position(&self, p: &Tuple) -> (f64, f64) {
+        let (dx, dz) = self.cell_size();
+        ((p.x + self.scale.x / 2.0) / dx, (p.z + self.scale.z / 2.0) / dz)
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Tuple::point(-self.scale.x / 2.0, self.min_height * self.scale.y, -self.scale.z / 2.0),
+            Tuple::point(self.scale.x / 2.0, self.max_height * self.scale.y, self.scale.z / 2.0)
+        )
+    }
+
+    fn intersect_cell(&self, r: &Ray, i: usize, j: usize, t_min: f64, t_max: f64, result: &mut Vec<f64>) {
+        let p00 = self.vertex(i, j);
+        let p10 = self.vertex(i + 1, j);
+        let p01 = self.vertex(i, j + 1);
+        let p11 = self.vertex(i + 1, j + 1);
+
+        for (a, b, c) in [(&p00, &p10, &p11), (&p00, &p11, &p01)].iter() {
+            if let Some(t) = intersect_triangle(r, a, b, c) {
+                if t >= t_min && t < t_max {
+                    result.push(t);
+                }
+            }
+        }
+    }
+}
+
+impl Shape for Heightfield {
+    fn normal_at(&self, p: &Tuple) -> Tuple {
+        let op = self.inv_transformation * *p;
+
+        // Bilinear interpolation of the surrounding vertex normals.
+        let (gx, gz) = self.grid_position(&op);
+        let i = (gx.floor().max(0.0) as usize).min(self.width - 2);
+        let j = (gz.floor().max(0.0) as usize).min(self.depth - 2);
+        let fx = (gx - i as f64).clamp(0.0, 1.0);
+        let fz = (gz - j as f64).clamp(0.0, 1.0);
+
+        let n = |i: usize, j: usize| self.normals[j * self.width + i];
+        let on = n(i, j) * ((1.0 - fx) * (1.0 - fz))
+            + n(i + 1, j) * (fx * (1.0 - fz))
+            + n(i, j + 1) * ((1.0 - fx) * fz)
+            + n(i + 1, j + 1) * (fx * fz);
+
+        let mut wn = self.inv_transformation.transpose() * on;
+        wn.w = 0.0;
+        wn.normalize()
+    }
+
+    fn intersect(&self, r: &Ray) -> Vec<Intersection> {
+        let mut result = Vec::new();
+
+        let ray = r.transform(&self.inv_transformation);
+        let (t_start, t_end) = match self.local_bounds().range(&ray, f64::NEG_INFINITY, f64::INFINITY) {
+            Some(range) => range,
+            None => return result,
+        };
+
+        // Walk the cells below the ray between entering and leaving the
+        // bounding box.
+        let (dx, dz) = self.cell_size();
+        let (gx, gz) = self.grid_position(&ray.position(t_start));
+        let (gdx, gdz) = (ray.direction.x / dx, ray.direction.z / dz);
+
+        let mut i = (gx.floor().max(0.0) as usize).min(self.width - 2);
+        let mut j = (gz.floor().max(0.0) as usize).min(self.depth - 2);
+
+        let step = |d: f64| if d > 0.0 { 1 } else { -1 };
+        let next_boundary = |g: f64, cell: usize, d: f64| {
+            if d == 0.0 {
+                f64::INFINITY
+            } else if d > 0.0 {
+                t_start + (cell as f64 + 1.0 - g) / d
+            } else {
+                t_start + (cell as f64 - g) / d
+            }
+        };
+        let mut t_next_x = next_boundary(gx, i, gdx);
+        let mut t_next_z = next_boundary(gz, j, gdz);
+        let t_delta_x = if gdx == 0.0 { f64::INFINITY } else { 1.0 / gdx.abs() };
+        let t_delta_z = if gdz == 0.0 { f64::INFINITY } else { 1.0 / gdz.abs() };
+
+        let mut ts = Vec::new();
+        let mut t = t_start - EPSILON;
+        loop {
+            let t_exit = t_next_x.min(t_next_z).min(t_end + EPSILON);
+            self.intersect_cell(&ray, i, j, t, t_exit, &mut ts);
+            if t_exit >= t_end {
+                break;
+            }
+            t = t_exit;
+
+            if t_next_x < t_next_z {
+                let next = i as i64 + step(gdx);
+                if next < 0 || next > self.width as i64 - 2 {
+                    break;
+                }
+                i = next as usize;
+                t_next_x += t_delta_x;
+            } else {
+                let next = j as i64 + step(gdz);
+                if next < 0 || next > self.depth as i64 - 2 {
+                    break;
+                }
+                j = next as usize;
+                t_next_z += t_delta_z;
+            }
+        }
+
+        for t in ts.into_iter() {
+            result.push(Intersection::new(t, self));
+        }
+        result
+    }
+
+    fn uv_coordinates(&self, p: &Tuple) -> Tuple {
+        let u = p.x / self.scale.x + 0.5;
+        let v = p.z / self.scale.z + 0.5;
+        Tuple::point(u, v, 0.0)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.local_bounds().transform(&self.transformation)
+    }
+
+    fn get_id(&self) -> &Uuid {
+        &self.id
+    }
+
+    fn set_transformation(&mut self, t: Matrix4) {
+        self.inv_transformation = t.invert();
+        self.transformation = t;
+    }
+    fn get_transformation(&self) -> &Matrix4 {
+        &self.transformation
+    }
+    fn get_inverse_transformation(&self) -> &Matrix4 {
+        &self.inv_transformation
+    }
+
+    fn set_material(&mut self, mat: Material) {
+        self.material = mat;
+    }
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+}
+
+fn intersect_triangle(r: &Ray, p1: &Tuple, p2: &Tuple, p3: &Tuple) -> Option<f64> {
+    let e1 = *p2 - *p1;
+    let e2 = *p3 - *p1;
+    let dir_cross_e2 = r.direction.cross(&e2);
+    let det = e1.dot(&dir_cross_e2);
+    if det.abs() < EPSILON * EPSILON {
+        return None;
+    }
+
+    let f = 1.0 / det;
+    let p1_to_origin = r.origin - *p1;
+    let u = f * p1_to_origin.dot(&dir_cross_e2);
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let origin_cross_e1 = p1_to_origin.cross(&e1);
+    let v = f * r.direction.dot(&origin_cross_e1);
+    if v < 0.0 || (u + v) > 1.0 {
+        return None;
+    }
+
+    Some(f * e2.dot(&origin_cross_e1))
+}
+
+
+// Grid size and heights between 0 and 1 of a PNG image, converted to
+// grayscale if it has colors. Image rows run along z, starting at the
+// far end.
+pub fn decode_png<R: Read>(data: R) -> (usize, usize, Vec<f64>) {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info().expect("Could not decode the heightfield image!");
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).expect("Could not decode the heightfield image!");
+
+    let (width, depth) = (info.width as usize, info.height as usize);
+    let samples = info.color_type.samples();
+    let wide = info.bit_depth == png::BitDepth::Sixteen;
+    let sample = |index: usize| -> f64 {
+        if wide {
+            u16::from_be_bytes([buffer[2 * index], buffer[2 * index + 1]]) as f64 / 65535.0
+        } else {
+            buffer[index] as f64 / 255.0
+        }
+    };
+
+    let mut heights = vec![0.0; width * depth];
+    for row in 0..depth {
+        for column in 0..width {
+            let index = (row * width + column) * samples;
+            let value = match info.color_type {
+                png::ColorType::Rgb | png::ColorType::Rgba => {
+                    0.2126 * sample(index) + 0.7152 * sample(index + 1) + 0.0722 * sample(index + 2)
+                },
+                _ => sample(index),
+            };
+            heights[(depth - 1 - row) * width + column] = value;
+        }
+    }
+
+    (width, depth, heights)
+}
+
+pub fn load_heightfield(path: &Path, scale: Tuple) -> Heightfield {
+    let file = File::open(path)
+        .unwrap_or_else(|_| panic!("Could not read the heightfield image {:?}!", path));
+    let (width, depth, heights) = decode_png(file);
+    Heightfield::new(Matrix4::identity(), Material::default(), width, depth, heights, scale)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::equal;
+
+    // Ridge along z rising linearly from x = -1 to x = 0 and falling
+    // again to x = 1.
+    fn ridge() -> Heightfield {
+        let heights = vec![
+            0.0, 1.0, 0.0,
+            0.0, 1.0, 0.0,
+            0.0, 1.0, 0.0,
+        ];
+        Heightfield::new(Matrix4::identity(), Material::default(), 3, 3, heights, Tuple::vector(2.0, 1.0, 2.0))
+    }
+
+    #[test]
+    fn test_intersect() {
+        let h = ridge();
+
+        let r = Ray::new(0.0, 5.0, 0.3, 0.0, -1.0, 0.0);
+        let ts: Vec<f64> = h.intersect(&r).iter().map(|i| i.t).collect();
+        assert_eq!(ts.len(), 1);
+        assert!(equal(ts[0], 4.0));
+
+        let r = Ray::new(-0.5, 5.0, -0.7, 0.0, -1.0, 0.0);
+        let ts: Vec<f64> = h.intersect(&r).iter().map(|i| i.t).collect();
+        assert_eq!(ts.len(), 1);
+        assert!(equal(ts[0], 4.5));
+
+        // Horizontal ray crossing the ridge at half its height.
+        let r = Ray::new(-5.0, 0.5, 0.2, 1.0, 0.0, 0.0);
+        let ts: Vec<f64> = h.intersect(&r).iter().map(|i| i.t).collect();
+        assert_eq!(ts.len(), 2);
+        assert!(equal(ts[0], 4.5));
+        assert!(equal(ts[1], 5.5));
+
+        // Over the top and outside the grid.
+        let r = Ray::new(-5.0, 1.5, 0.2, 1.0, 0.0, 0.0);
+        assert_eq!(h.intersect(&r).len(), 0);
+        let r = Ray::new(2.0, 5.0, 0.0, 0.0, -1.0, 0.0);
+        assert_eq!(h.intersect(&r).len(), 0);
+    }
+
+    #[test]
+    fn test_walk_many_cells() {
+        // Flat field, a diagonal ray through it only hits it once.
+        let heights = vec![0.5; 50 * 40];
+        let h = Heightfield::new(Matrix4::identity(), Material::default(), 50, 40, heights, Tuple::vector(10.0, 2.0, 10.0));
+        let r = Ray::new(-6.0, 6.0, -4.0, 1.0, -1.0, 0.7);
+        let ts: Vec<f64> = h.intersect(&r).iter().map(|i| i.t).collect();
+        assert_eq!(ts.len(), 1);
+        assert!(equal(ts[0], 5.0));
+    }
+
+    #[test]
+    fn test_normal_at() {
+        let h = ridge();
+        let slope = Tuple::vector(-1.0, 1.0, 0.0).normalize();
+        assert_eq!(h.normal_at(&Tuple::point(-1.0, 0.0, 0.0)), slope);
+        // Normals are blended towards the top of the ridge.
+        assert_eq!(h.normal_at(&Tuple::point(0.0, 1.0, 0.0)), Tuple::vector(0.0, 1.0, 0.0));
+        let n = h.normal_at(&Tuple::point(-0.5, 0.5, 0.0));
+        assert_eq!(n, Tuple::vector(-0.5_f64.sqrt(), 0.5_f64.sqrt() + 1.0, 0.0).normalize());
+    }
+
+    #[test]
+    fn test_uv_coordinates() {
+        let h = ridge();
+        assert_eq!(h.uv_coordinates(&Tuple::point(-1.0, 0.0, 1.0)), Tuple::point(0.0, 1.0, 0.0));
+        assert_eq!(h.uv_coordinates(&Tuple::point(0.5, 0.0, 0.0)), Tuple::point(0.75, 0.5, 0.0));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let b = ridge().bounds();
+        assert_eq!(b.min, Tuple::point(-1.0, 0.0, -1.0));
+        assert_eq!(b.max, Tuple::point(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_decode_png() {
+        let mut data = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut data, 2, 2);
+            encoder.set_color(png::ColorType::Grayscale);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(&[0, 255, 51, 102]).unwrap();
+        }
+
+        let (width, depth, heights) = decode_png(&data[..]);
+        assert_eq!((width, depth), (2, 2));
+        assert_eq!(heights, vec![0.2, 0.4, 0.0, 1.0]);
+    }
+}
@@ -0,0 +1,288 @@
+use crate::linalg::matrix4::Matrix4;
+use crate::linalg::tuple::Tuple;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sdf::Sdf;
+use crate::shapes::bounds::BoundingBox;
+use crate::shapes::shape::Shape;
+use crate::world::Intersection;
+
+use uuid::Uuid;
+
+
+static MAX_STEPS: usize = 512;
+static MAX_DISTANCE: f64 = 1000.0;
+static MIN_STEP: f64 = 0.0001;
+static BISECTION_STEPS: usize = 24;
+static NORMAL_DELTA: f64 = 0.0001;
+
+
+// Surface implicitly defined by a signed distance function, rendered by
+// sphere tracing in object space. The march runs through the whole
+// bounding box and reports every crossing of the surface, entering as
+// well as leaving, so the shape refracts like any other closed shape.
+#[derive(Clone)]
+pub struct Implicit {
+    pub id: Uuid,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
+    pub material: Material,
+
+    pub sdf: Sdf,
+}
+
+impl Implicit {
+    #[allow(dead_code)]
+    pub fn new(transformation: Matrix4, material: Material, sdf: Sdf) -> Implicit {
+        Implicit {
+            id: Uuid::new_v4(),
+            inv_transformation: transformation.invert(),
+            transformation: transformation,
+            material: material,
+            sdf: sdf,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn default() -> Implicit {
+        Implicit {
+            id: Uuid::new_v4(),
+            inv_transformation: Matrix4::identity(),
+            transformation: Matrix4::identity(),
+            material: Material::default(),
+            sdf: Sdf::Sphere { radius: 1.0 },
+        }
+    }
+
+    // Narrow down a sign change of the distance between t0 and t1.
+    fn bisect(&self, ray: &Ray, mut t0: f64, mut t1: f64, inside: bool) -> f64 {
+        for _ in 0..BISECTION_STEPS {
+            let t = (t0 + t1) / 2.0;
+            if (self.sdf.distance(&ray.position(t)) < 0.0) == inside {
+                t0 = t;
+            } else {
+                t1 = t;
+            }
+        }
+        (t0 + t1) / 2.0
+    }
+}
+
+impl Shape for Implicit {
+    fn normal_at(&self, p: &Tuple) -> Tuple {
+        let op = self.inv_transformation * *p;
+
+        // Tetrahedral central differences of the distance.
+        let h = NORMAL_DELTA;
+        let mut on = Tuple::vector(0.0, 0.0, 0.0);
+        for k in [
+            Tuple::vector(1.0, -1.0, -1.0),
+            Tuple::vector(-1.0, -1.0, 1.0),
+            Tuple::vector(-1.0, 1.0, -1.0),
+            Tuple::vector(1.0, 1.0, 1.0),
+        ].iter() {
+            let d = self.sdf.distance(&(op + *k * h));
+            on += *k * d;
+        }
+
+        let mut wn = self.inv_transformation.transpose() * on;
+        wn.w = 0.0;
+        wn.normalize()
+    }
+
+    fn intersect(&self, r: &Ray) -> Vec<Intersection> {
+        let mut result = Vec::new();
+
+        // Distances are measured in object space, t stays the same in
+        // both spaces so steps are scaled by the direction's length.
+        let ray = r.transform(&self.inv_transformation);
+        let scale = ray.direction.magnitude();
+        let limit = MAX_DISTANCE / scale;
+        // Unbounded fields, like repetitions, are only marched in front
+        // of the origin, all steps would be spent behind it otherwise.
+        let bounds = self.sdf.bounds();
+        let t_min = if bounds.is_finite() { -limit } else { 0.0 };
+        let (t_start, t_end) = match bounds.range(&ray, t_min, limit) {
+            Some(range) => range,
+            None => return result,
+        };
+
+        // Start and stop a little outside the box, surfaces touching
+        // it would otherwise be missed.
+        let margin = 10.0 * MIN_STEP / scale;
+        let t_end = t_end + margin;
+        let mut t = t_start - margin;
+        let mut distance = self.sdf.distance(&ray.position(t));
+        let mut steps = 0;
+        while t < t_end && steps < MAX_STEPS {
+            let next_t = t + distance.abs().max(MIN_STEP) / scale;
+            let next_distance = self.sdf.distance(&ray.position(next_t));
+
+            let inside = distance < 0.0;
+            if inside != (next_distance < 0.0) {
+                result.push(Intersection::new(self.bisect(&ray, t, next_t, inside), self));
+            }
+
+            t = next_t;
+            distance = next_distance;
+            steps += 1;
+        }
+
+        // A ray starting inside an unbounded field, e.g. a refracted
+        // one, still needs the entry behind it to know which medium it
+        // is in. March backwards until the surface is found.
+        if !bounds.is_finite() {
+            let mut t = 0.0;
+            let mut distance = self.sdf.distance(&ray.position(t));
+            let mut steps = 0;
+            while distance < 0.0 && t > -limit && steps < MAX_STEPS {
+                let next_t = t - distance.abs().max(MIN_STEP) / scale;
+                let next_distance = self.sdf.distance(&ray.position(next_t));
+                if next_distance >= 0.0 {
+                    result.insert(0, Intersection::new(self.bisect(&ray, t, next_t, true), self));
+                }
+
+                t = next_t;
+                distance = next_distance;
+                steps += 1;
+            }
+        }
+
+        result
+    }
+
+    fn uv_coordinates(&self, p: &Tuple) -> Tuple {
+        // Spherical mapping around the origin.
+        let radius = (*p - Tuple::point(0.0, 0.0, 0.0)).magnitude();
+        let theta = p.x.atan2(p.z);
+        let phi = (p.y / radius).acos();
+        let raw_u = theta / (2.0 * std::f64::consts::PI);
+        let u = 1.0 - (raw_u + 0.5);
+        let v = 1.0 - phi / std::f64::consts::PI;
+        Tuple::point(u, v, 0.0)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.sdf.bounds().transform(&self.transformation)
+    }
+
+    fn get_id(&self) -> &Uuid {
+        &self.id
+    }
+
+    fn set_transformation(&mut self, t: Matrix4) {
+        self.inv_transformation = t.invert();
+        self.transformation = t;
+    }
+    fn get_transformation(&self) -> &Matrix4 {
+        &self.transformation
+    }
+    fn get_inverse_transformation(&self) -> &Matrix4 {
+        &self.inv_transformation
+    }
+
+    fn set_material(&mut self, mat: Material) {
+        self.material = mat;
+    }
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::sphere::Sphere;
+    use crate::utils::{equal, EPSILON};
+    use crate::world::{compute_intersection_data, compute_n1n2, World};
+
+    use std::sync::Arc;
+
+    #[test]
+    fn test_intersect() {
+        let s = Implicit::new(Matrix4::scaling(2.0, 2.0, 2.0), Material::default(), Sdf::Sphere { radius: 1.0 });
+        let r = Ray::new(0.0, 0.0, -5.0, 0.0, 0.0, 1.0);
+        let xs = s.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert!(equal(xs[0].t, 3.0));
+        assert!(equal(xs[1].t, 7.0));
+
+        // Starting inside still reports the entry behind the origin.
+        let r = Ray::new(0.0, 0.0, 0.0, 0.0, 0.0, 1.0);
+        let xs = s.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert!(equal(xs[0].t, -2.0));
+        assert!(equal(xs[1].t, 2.0));
+
+        let r = Ray::new(0.0, 2.5, -5.0, 0.0, 0.0, 1.0);
+        assert_eq!(s.intersect(&r).len(), 0);
+    }
+
+    #[test]
+    fn test_repetition() {
+        let sdf = Sdf::Repetition {
+            child: Arc::new(Sdf::Sphere { radius: 1.0 }),
+            period: Tuple::vector(4.0, 0.0, 4.0),
+        };
+        let s = Implicit::new(Matrix4::identity(), Material::default(), sdf);
+        let r = Ray::new(0.5, 0.0, -2.0, 0.0, 0.0, 1.0);
+        let xs = s.intersect(&r);
+        assert!(xs.iter().all(|x| x.t > -EPSILON));
+        assert!(equal(xs[0].t, 2.0 - 0.75_f64.sqrt()));
+        assert!(equal(xs[1].t, 2.0 + 0.75_f64.sqrt()));
+    }
+
+    #[test]
+    fn test_repetition_inside() {
+        let mut glass = Material::default();
+        glass.transparency = 1.0;
+        glass.refractive_index = 1.5;
+        let sdf = Sdf::Repetition {
+            child: Arc::new(Sdf::Sphere { radius: 1.0 }),
+            period: Tuple::vector(4.0, 0.0, 4.0),
+        };
+        let s = Implicit::new(Matrix4::identity(), glass, sdf);
+
+        // Like a refracted ray, starting just inside the surface.
+        let r = Ray::new(0.0, 0.0, -0.99, 0.0, 0.0, 1.0);
+        let mut xs = s.intersect(&r);
+        assert!(equal(xs[0].t, -0.01));
+        assert!(equal(xs[1].t, 1.99));
+        compute_intersection_data(&mut xs, &r);
+        compute_n1n2(&mut xs);
+        assert_eq!((xs[1].n1, xs[1].n2), (1.5, 1.0));
+    }
+
+    #[test]
+    fn test_matches_sphere() {
+        let implicit = Implicit::new(Matrix4::translation(0.5, 0.0, 0.0), Material::default(), Sdf::Sphere { radius: 1.0 });
+        let sphere = Sphere::new(Matrix4::translation(0.5, 0.0, 0.0), Material::default());
+
+        let p = Tuple::point(0.5 + 0.5_f64.sqrt(), 0.5_f64.sqrt(), 0.0);
+        assert_eq!(implicit.normal_at(&p), sphere.normal_at(&p));
+
+        let r = Ray::new(0.0, 0.3, -5.0, 0.1, 0.0, 1.0);
+        let a: Vec<f64> = implicit.intersect(&r).iter().map(|i| i.t).collect();
+        let b: Vec<f64> = sphere.intersect(&r).iter().map(|i| i.t).collect();
+        assert_eq!(a.len(), b.len());
+        for (ta, tb) in a.iter().zip(b.iter()) {
+            assert!(equal(*ta, *tb));
+        }
+    }
+
+    #[test]
+    fn test_refraction() {
+        let mut glass = Material::default();
+        glass.transparency = 1.0;
+        glass.refractive_index = 1.5;
+        let s = Implicit::new(Matrix4::identity(), glass, Sdf::Box { size: Tuple::vector(1.0, 1.0, 1.0) });
+
+        let world = World::new(vec![Box::new(s)], World::default().lights);
+        let r = Ray::new(0.0, 0.0, -5.0, 0.0, 0.0, 1.0);
+        let xs = world.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_eq!((xs[0].n1, xs[0].n2), (1.0, 1.5));
+        assert_eq!((xs[1].n1, xs[1].n2), (1.5, 1.0));
+    }
+}
@@ -1,7 +1,19 @@
 pub mod shape;
+pub mod bounds;
 
+pub mod cone;
 pub mod cube;
+pub mod csg;
 pub mod cylinder;
+pub mod disk;
+pub mod group;
+pub mod heightfield;
+pub mod implicit;
+pub mod moving;
 pub mod plane;
+pub mod quad;
+pub mod rectangle;
+pub mod smooth_triangle;
 pub mod sphere;
+pub mod torus;
 pub mod triangle;
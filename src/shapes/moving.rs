@@ -0,0 +1,242 @@
+use crate::linalg::decomposition::Decomposition;
+use crate::linalg::matrix4::Matrix4;
+use crate::linalg::tuple::Tuple;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::shapes::bounds::BoundingBox;
+use crate::shapes::shape::Shape;
+use crate::world::Intersection;
+
+use uuid::Uuid;
+
+
+// Number of steps between two keyframes at which the bounds are taken,
+// rotations sweep along curves the keyframe bounds alone would miss.
+const BOUNDS_STEPS: usize = 16;
+
+
+// Transformation changing over time, interpolated between keyframes.
+// Before the first and after the last keyframe it stays as it is.
+#[derive(Debug, Clone)]
+pub struct Keyframes {
+    frames: Vec<(f64, Decomposition)>,
+}
+
+impl Keyframes {
+    pub fn new(mut frames: Vec<(f64, Matrix4)>) -> Keyframes {
+        if frames.is_empty() {
+            panic!("Keyframes need at least one transformation!");
+        }
+        frames.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        Keyframes {
+            frames: frames.iter().map(|(t, m)| (*t, Decomposition::new(m))).collect(),
+        }
+    }
+
+    pub fn at(&self, time: f64) -> Matrix4 {
+        let i = self.frames.iter().position(|f| f.0 > time).unwrap_or(self.frames.len());
+        if i == 0 {
+            return self.frames[0].1.to_matrix();
+        }
+        if i == self.frames.len() {
+            return self.frames[i - 1].1.to_matrix();
+        }
+
+        let (t0, a) = &self.frames[i - 1];
+        let (t1, b) = &self.frames[i];
+        a.interpolate(b, (time - t0) / (t1 - t0)).to_matrix()
+    }
+
+    // Times at which the transformation is sampled to bound the motion.
+    fn sample_times(&self) -> Vec<f64> {
+        let mut result = vec![self.frames[0].0];
+        for w in self.frames.windows(2) {
+            for k in 1..=BOUNDS_STEPS {
+                result.push(w[0].0 + (w[1].0 - w[0].0) * k as f64 / BOUNDS_STEPS as f64);
+            }
+        }
+        result
+    }
+}
+
+
+// An object moving along keyframes, for motion blur. The child is kept
+// where it is at the first keyframe, rays are moved into that position
+// instead of moving the child for every ray. Intersections remember the
+// motion so normals and patterns can be moved along.
+pub struct Moving {
+    pub id: Uuid,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
+    pub material: Material,
+
+    pub child: Box<dyn Shape + Sync>,
+    pub child_transformation: Matrix4,
+    pub keyframes: Keyframes,
+    // Full transformation of the child at the first keyframe.
+    reference: Matrix4,
+    inv_reference: Matrix4,
+    bounds: BoundingBox,
+}
+
+impl Moving {
+    pub fn new(child: Box<dyn Shape + Sync>, keyframes: Keyframes) -> Moving {
+        let mut result = Moving {
+            id: Uuid::new_v4(),
+            transformation: Matrix4::identity(),
+            inv_transformation: Matrix4::identity(),
+            material: Material::default(),
+            child_transformation: *child.get_transformation(),
+            child: child,
+            keyframes: keyframes,
+            reference: Matrix4::identity(),
+            inv_reference: Matrix4::identity(),
+            bounds: BoundingBox::empty(),
+        };
+        result.set_transformation(Matrix4::identity());
+        result
+    }
+
+    // Transformation from the reference position to the one at the
+    // given time.
+    pub fn motion_at(&self, time: f64) -> Matrix4 {
+        self.transformation * self.keyframes.at(time) * self.child_transformation * self.inv_reference
+    }
+}
+
+impl Shape for Moving {
+    fn normal_at(&self, _p: &Tuple) -> Tuple {
+        panic!("Calling normal_at() on a moving object, intersections refer to its child!");
+    }
+
+    fn intersect(&self, r: &Ray) -> Vec<Intersection> {
+        let inv_motion = self.motion_at(r.time).invert();
+        let mut result = self.child.intersect(&r.transform(&inv_motion));
+        // Moving objects inside moving groups move along with both.
+        for i in result.iter_mut() {
+            i.inv_motion = Some(match i.inv_motion {
+                Some(m) => m * inv_motion,
+                None => inv_motion,
+            });
+        }
+        result
+    }
+
+    fn uv_coordinates(&self, _p: &Tuple) -> Tuple {
+        panic!("Calling uv_coordinates() on a moving object, intersections refer to its child!");
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+
+    fn includes(&self, id: &Uuid) -> bool {
+        &self.id == id || self.child.includes(id)
+    }
+
+    fn get_id(&self) -> &Uuid {
+        &self.id
+    }
+
+    // The keyframes are applied on top of the child's own transformation,
+    // and this transformation on top of them like that of a group.
+    fn set_transformation(&mut self, t: Matrix4) {
+        self.inv_transformation = t.invert();
+        self.transformation = t;
+        self.reference = t * self.keyframes.at(f64::NEG_INFINITY) * self.child_transformation;
+        self.inv_reference = self.reference.invert();
+        self.child.set_transformation(self.reference);
+
+        let child_bounds = self.child.bounds();
+        self.bounds = BoundingBox::empty();
+        for time in self.keyframes.sample_times() {
+            self.bounds = self.bounds.merge(&child_bounds.transform(&self.motion_at(time)));
+        }
+    }
+    fn get_transformation(&self) -> &Matrix4 {
+        &self.transformation
+    }
+
+    fn get_inverse_transformation(&self) -> &Matrix4 {
+        &self.inv_transformation
+    }
+
+    fn set_material(&mut self, mat: Material) {
+        self.child.set_material(mat.clone());
+        self.material = mat;
+    }
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::sphere::Sphere;
+    use crate::world::compute_intersection_data;
+
+    fn moving_sphere() -> Moving {
+        let keyframes = Keyframes::new(vec![
+            (0.0, Matrix4::translation(0.0, 0.0, 0.0)),
+            (1.0, Matrix4::translation(4.0, 0.0, 0.0) * Matrix4::rotation_z(90.0)),
+        ]);
+        Moving::new(Box::new(Sphere::default()), keyframes)
+    }
+
+    #[test]
+    fn test_keyframes() {
+        let k = Keyframes::new(vec![
+            (2.0, Matrix4::translation(4.0, 0.0, 0.0)),
+            (0.0, Matrix4::identity()),
+            (3.0, Matrix4::translation(4.0, 2.0, 0.0)),
+        ]);
+        assert_eq!(k.at(-1.0), Matrix4::identity());
+        assert_eq!(k.at(0.5), Matrix4::translation(1.0, 0.0, 0.0));
+        assert_eq!(k.at(2.5), Matrix4::translation(4.0, 1.0, 0.0));
+        assert_eq!(k.at(7.0), Matrix4::translation(4.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn test_intersect() {
+        let s = moving_sphere();
+        let mut r = Ray::new(0.0, 0.0, -5.0, 0.0, 0.0, 1.0);
+        assert_eq!(s.intersect(&r).len(), 2);
+
+        r.time = 1.0;
+        assert_eq!(s.intersect(&r).len(), 0);
+        r.origin = Tuple::point(4.0, 0.5, -5.0);
+        let mut ix = s.intersect(&r);
+        assert_eq!(ix.len(), 2);
+
+        // Normals turn with the object.
+        compute_intersection_data(&mut ix, &r);
+        let n = Tuple::vector(0.0, 0.5, -0.75_f64.sqrt());
+        assert_eq!(ix[0].normalv, n);
+        assert_eq!(ix[0].reference_point, Tuple::point(0.5, 0.0, -0.75_f64.sqrt()));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let keyframes = Keyframes::new(vec![
+            (0.0, Matrix4::identity()),
+            (1.0, Matrix4::translation(4.0, 0.0, 0.0)),
+        ]);
+        let s = Moving::new(Box::new(Sphere::default()), keyframes);
+        let b = s.bounds();
+        assert_eq!(b.min, Tuple::point(-1.0, -1.0, -1.0));
+        assert_eq!(b.max, Tuple::point(5.0, 1.0, 1.0));
+
+        // Bounds of a rotating object include the space it sweeps, not
+        // only where it is at the keyframes.
+        let keyframes = Keyframes::new(vec![
+            (0.0, Matrix4::identity()),
+            (1.0, Matrix4::rotation_z(170.0)),
+        ]);
+        let child = Sphere::new(Matrix4::translation(2.0, 0.0, 0.0), Material::default());
+        let s = Moving::new(Box::new(child), keyframes);
+        assert!(s.bounds().min.x < 2.0 * 170.0_f64.to_radians().cos() - 0.99);
+        assert!(s.bounds().max.y > 2.99);
+    }
+}
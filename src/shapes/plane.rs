@@ -1,8 +1,9 @@
 use crate::color::Color;
-use crate::linalg::matrix::Matrix;
+use crate::linalg::matrix4::Matrix4;
 use crate::linalg::tuple::Tuple;
 use crate::material::Material;
 use crate::ray::Ray;
+use crate::shapes::bounds::BoundingBox;
 use crate::shapes::shape::Shape;
 use crate::utils;
 use crate::world::Intersection;
@@ -13,14 +14,14 @@ use uuid::Uuid;
 #[derive(Clone)]
 pub struct Plane {
     pub id: Uuid,
-    pub transformation: Matrix,
-    pub inv_transformation: Matrix,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
     pub material: Material,
 }
 
 impl Plane {
     #[allow(dead_code)]
-    pub fn new(transformation: Matrix, material: Material) -> Plane {
+    pub fn new(transformation: Matrix4, material: Material) -> Plane {
         Plane {
             id: Uuid::new_v4(),
             inv_transformation: transformation.invert(),
@@ -32,8 +33,8 @@ impl Plane {
     pub fn default() -> Plane {
         Plane {
             id: Uuid::new_v4(),
-            transformation: Matrix::identity(4),
-            inv_transformation: Matrix::identity(4),
+            transformation: Matrix4::identity(),
+            inv_transformation: Matrix4::identity(),
             material: Material::new(
                 Some(Color::new(0.0, 0.0, 1.0)),
                 None,
@@ -46,7 +47,7 @@ impl Plane {
 impl Shape for Plane {
     fn normal_at(&self, _: &Tuple) -> Tuple {
         let p = Tuple::vector(0.0, 1.0, 0.0);
-        self.inv_transformation.multiply_tuple(&p).normalize()
+        (self.inv_transformation * p).normalize()
     }
 
     fn intersect(&self, r: &Ray) -> Vec<Intersection> {
@@ -67,19 +68,23 @@ impl Shape for Plane {
         Tuple::point(u, v, 0.0)
     }
 
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::infinite()
+    }
+
     fn get_id(&self) -> &Uuid {
         &self.id
     }
 
-    fn set_transformation(&mut self, t: Matrix) {
+    fn set_transformation(&mut self, t: Matrix4) {
         self.inv_transformation = t.invert();
         self.transformation = t;
     }
-    fn get_transformation(&self) -> &Matrix {
+    fn get_transformation(&self) -> &Matrix4 {
         &self.transformation
     }
 
-    fn get_inverse_transformation(&self) -> &Matrix {
+    fn get_inverse_transformation(&self) -> &Matrix4 {
         &self.inv_transformation
     }
 
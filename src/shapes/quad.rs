@@ -0,0 +1,211 @@
+use crate::linalg::matrix4::Matrix4;
+use crate::linalg::tuple::Tuple;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::shapes::bounds::BoundingBox;
+use crate::shapes::shape::Shape;
+use crate::utils::EPSILON;
+use crate::world::Intersection;
+
+use uuid::Uuid;
+
+
+// Parallelogram spanned by the corner p1 and its two neighbours p2 and
+// p3, the fourth corner is p2 + p3 - p1. Like a triangle it keeps its
+// corners in world space.
+#[derive(Clone)]
+pub struct Quad {
+    pub id: Uuid,
+    pub original_p1: Tuple,
+    pub original_p2: Tuple,
+    pub original_p3: Tuple,
+    pub p1: Tuple,
+    pub e1: Tuple,
+    pub e2: Tuple,
+    pub normal: Tuple,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
+    pub material: Material,
+}
+
+impl Quad {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple, transformation: Matrix4, material: Material) -> Quad {
+        let mut result = Quad {
+            id: Uuid::new_v4(),
+            original_p1: p1,
+            original_p2: p2,
+            original_p3: p3,
+            p1: p1,
+            e1: p1,
+            e2: p1,
+            normal: p1,
+            inv_transformation: transformation.invert(),
+            transformation: transformation,
+            material: material,
+        };
+        result.update();
+        result
+    }
+
+    // Distance along the ray and position u/v of the hit, measured
+    // along e1 and e2.
+    pub fn intersect_uv(&self, r: &Ray) -> Option<(f64, f64, f64)> {
+        // Ray is parallel to the quad.
+        let dir_cross_e2 = r.direction.cross(&self.e2);
+        let det = self.e1.dot(&dir_cross_e2);
+        if det.abs() < EPSILON {
+            return None;
+        }
+
+        let f = 1.0 / det;
+        let p1_to_origin = r.origin - self.p1;
+        let u = f * p1_to_origin.dot(&dir_cross_e2);
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let origin_cross_e1 = p1_to_origin.cross(&self.e1);
+        let v = f * r.direction.dot(&origin_cross_e1);
+        if !(0.0..=1.0).contains(&v) {
+            return None;
+        }
+
+        let t = f * self.e2.dot(&origin_cross_e1);
+        Some((t, u, v))
+    }
+
+    pub fn update(&mut self) {
+        self.p1 = self.transformation * self.original_p1;
+        let p2 = self.transformation * self.original_p2;
+        let p3 = self.transformation * self.original_p3;
+
+        self.e1 = p2 - self.p1;
+        self.e2 = p3 - self.p1;
+        self.normal = self.e2.cross(&self.e1).normalize();
+    }
+}
+
+impl Shape for Quad {
+    fn normal_at(&self, _p: &Tuple) -> Tuple {
+        self.normal
+    }
+
+    fn intersect(&self, r: &Ray) -> Vec<Intersection> {
+        let mut result = Vec::new();
+        if let Some((t, u, v)) = self.intersect_uv(r) {
+            result.push(Intersection::with_uv(t, self, u, v));
+        }
+        result
+    }
+
+    fn uv_coordinates(&self, p: &Tuple) -> Tuple {
+        // Patterns see the point in object space, so decompose it along
+        // the untransformed edges.
+        let e1 = self.original_p2 - self.original_p1;
+        let e2 = self.original_p3 - self.original_p1;
+        let d = *p - self.original_p1;
+
+        let e11 = e1.dot(&e1);
+        let e12 = e1.dot(&e2);
+        let e22 = e2.dot(&e2);
+        let det = e11 * e22 - e12 * e12;
+        let d1 = d.dot(&e1);
+        let d2 = d.dot(&e2);
+
+        let u = (e22 * d1 - e12 * d2) / det;
+        let v = (e11 * d2 - e12 * d1) / det;
+        Tuple::point(u, v, 0.0)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut result = BoundingBox::empty();
+        result.add_point(&self.p1);
+        result.add_point(&(self.p1 + self.e1));
+        result.add_point(&(self.p1 + self.e2));
+        result.add_point(&(self.p1 + self.e1 + self.e2));
+        result
+    }
+
+    fn get_id(&self) -> &Uuid {
+        &self.id
+    }
+
+    fn set_transformation(&mut self, t: Matrix4) {
+        self.inv_transformation = t.invert();
+        self.transformation = t;
+        self.update();
+    }
+
+    fn get_transformation(&self) -> &Matrix4 {
+        &self.transformation
+    }
+
+    fn get_inverse_transformation(&self) -> &Matrix4 {
+        &self.inv_transformation
+    }
+
+    fn set_material(&mut self, mat: Material) {
+        self.material = mat;
+    }
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quad() -> Quad {
+        Quad::new(
+            Tuple::point(0.0, 0.0, 0.0),
+            Tuple::point(2.0, 0.0, 0.0),
+            Tuple::point(1.0, 1.0, 0.0),
+            Matrix4::identity(),
+            Material::default()
+        )
+    }
+
+    #[test]
+    fn test_intersect() {
+        let q = quad();
+        let cases = vec![
+            (Ray::new(2.5, 0.5, -2.0, 0.0, 0.0, 1.0), 1),
+            (Ray::new(0.5, 0.5, -2.0, 0.0, 0.0, 1.0), 1),
+            (Ray::new(0.25, 0.5, -2.0, 0.0, 0.0, 1.0), 0),
+            (Ray::new(3.0, 0.5, -2.0, 0.0, 0.0, 1.0), 0),
+            (Ray::new(1.0, 1.5, -2.0, 0.0, 0.0, 1.0), 0),
+            (Ray::new(1.0, 0.5, -2.0, 1.0, 0.0, 0.0), 0),
+        ];
+        for (r, count) in cases.into_iter() {
+            assert_eq!(q.intersect(&r).len(), count);
+        }
+
+        let xs = q.intersect(&Ray::new(2.5, 0.5, -2.0, 0.0, 0.0, 1.0));
+        assert_eq!(xs[0].t, 2.0);
+        assert_eq!(xs[0].u, 1.0);
+        assert_eq!(xs[0].v, 0.5);
+    }
+
+    #[test]
+    fn test_normal_at() {
+        let q = quad();
+        assert_eq!(q.normal_at(&Tuple::point(1.0, 0.5, 0.0)), Tuple::vector(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn test_uv_coordinates() {
+        let q = quad();
+        assert_eq!(q.uv_coordinates(&Tuple::point(2.5, 0.5, 0.0)), Tuple::point(1.0, 0.5, 0.0));
+        assert_eq!(q.uv_coordinates(&Tuple::point(1.0, 1.0, 0.0)), Tuple::point(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let q = quad();
+        let b = q.bounds();
+        assert_eq!(b.min, Tuple::point(0.0, 0.0, 0.0));
+        assert_eq!(b.max, Tuple::point(3.0, 1.0, 0.0));
+    }
+}
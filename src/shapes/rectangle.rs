@@ -0,0 +1,142 @@
+use crate::linalg::matrix4::Matrix4;
+use crate::linalg::tuple::Tuple;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::shapes::bounds::BoundingBox;
+use crate::shapes::shape::Shape;
+use crate::utils::EPSILON;
+use crate::world::Intersection;
+
+use uuid::Uuid;
+
+
+// Square in the xz plane spanning from -1 to 1 on both axes, the finite
+// counterpart of a plane. Like a cube it is sized by its transformation.
+#[derive(Clone)]
+pub struct Rectangle {
+    pub id: Uuid,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
+    pub material: Material,
+}
+
+impl Rectangle {
+    #[allow(dead_code)]
+    pub fn new(transformation: Matrix4, material: Material) -> Rectangle {
+        Rectangle {
+            id: Uuid::new_v4(),
+            inv_transformation: transformation.invert(),
+            transformation: transformation,
+            material: material,
+        }
+    }
+
+    pub fn default() -> Rectangle {
+        Rectangle {
+            id: Uuid::new_v4(),
+            inv_transformation: Matrix4::identity(),
+            transformation: Matrix4::identity(),
+            material: Material::default(),
+        }
+    }
+}
+
+impl Shape for Rectangle {
+    fn normal_at(&self, _p: &Tuple) -> Tuple {
+        let on = Tuple::vector(0.0, 1.0, 0.0);
+        let mut wn = self.inv_transformation.transpose() * on;
+        wn.w = 0.0;
+        wn.normalize()
+    }
+
+    fn intersect(&self, r: &Ray) -> Vec<Intersection> {
+        let mut result = Vec::new();
+
+        let ray = r.transform(&self.inv_transformation);
+        if ray.direction.y.abs() > EPSILON {
+            let t = -ray.origin.y / ray.direction.y;
+            let x = ray.origin.x + t * ray.direction.x;
+            let z = ray.origin.z + t * ray.direction.z;
+            if x.abs() <= 1.0 && z.abs() <= 1.0 {
+                result.push(Intersection::new(t, self));
+            }
+        }
+
+        result
+    }
+
+    fn uv_coordinates(&self, p: &Tuple) -> Tuple {
+        let u = (p.x + 1.0) / 2.0;
+        let v = (p.z + 1.0) / 2.0;
+        Tuple::point(u, v, 0.0)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(Tuple::point(-1.0, 0.0, -1.0), Tuple::point(1.0, 0.0, 1.0))
+            .transform(&self.transformation)
+    }
+
+    fn get_id(&self) -> &Uuid {
+        &self.id
+    }
+
+    fn set_transformation(&mut self, t: Matrix4) {
+        self.inv_transformation = t.invert();
+        self.transformation = t;
+    }
+    fn get_transformation(&self) -> &Matrix4 {
+        &self.transformation
+    }
+    fn get_inverse_transformation(&self) -> &Matrix4 {
+        &self.inv_transformation
+    }
+
+    fn set_material(&mut self, mat: Material) {
+        self.material = mat;
+    }
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intersect() {
+        let rect = Rectangle::new(Matrix4::scaling(2.0, 1.0, 1.0), Material::default());
+        let cases = vec![
+            (Ray::new(0.0, 1.0, 0.0, 0.0, -1.0, 0.0), 1),
+            (Ray::new(1.5, 1.0, 0.5, 0.0, -1.0, 0.0), 1),
+            (Ray::new(1.5, 1.0, 1.5, 0.0, -1.0, 0.0), 0),
+            (Ray::new(2.5, 1.0, 0.0, 0.0, -1.0, 0.0), 0),
+            (Ray::new(0.0, 1.0, 0.0, 1.0, 0.0, 0.0), 0),
+        ];
+        for (r, count) in cases.into_iter() {
+            assert_eq!(rect.intersect(&r).len(), count);
+        }
+    }
+
+    #[test]
+    fn test_normal_at() {
+        let rect = Rectangle::new(Matrix4::rotation_z(90.0), Material::default());
+        assert_eq!(rect.normal_at(&Tuple::point(0.0, 0.5, 0.0)), Tuple::vector(-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_uv_coordinates() {
+        let rect = Rectangle::default();
+        assert_eq!(rect.uv_coordinates(&Tuple::point(-1.0, 0.0, -1.0)), Tuple::point(0.0, 0.0, 0.0));
+        assert_eq!(rect.uv_coordinates(&Tuple::point(0.5, 0.0, 0.0)), Tuple::point(0.75, 0.5, 0.0));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let rect = Rectangle::new(Matrix4::rotation_x(90.0), Material::default());
+        let b = rect.bounds();
+        assert_eq!(b.min, Tuple::point(-1.0, -1.0, 0.0));
+        assert_eq!(b.max, Tuple::point(1.0, 1.0, 0.0));
+    }
+}
@@ -1,7 +1,8 @@
-use crate::linalg::matrix::Matrix;
+use crate::linalg::matrix4::Matrix4;
 use crate::linalg::tuple::Tuple;
 use crate::material::Material;
 use crate::ray::Ray;
+use crate::shapes::bounds::BoundingBox;
 use crate::world::Intersection;
 
 use uuid::Uuid;
@@ -9,14 +10,26 @@ use uuid::Uuid;
 
 pub trait Shape {
     fn normal_at(&self, p: &Tuple) -> Tuple;
+    fn normal_at_hit(&self, p: &Tuple, _hit: &Intersection) -> Tuple {
+        self.normal_at(p)
+    }
     fn intersect(&self, r: &Ray) -> Vec<Intersection>;
     fn uv_coordinates(&self, p: &Tuple) -> Tuple;
 
+    // World space bounds of the shape. Shapes without finite bounds
+    // are tested against every ray.
+    fn bounds(&self) -> BoundingBox;
+
     fn get_id(&self) -> &Uuid;
 
-    fn set_transformation(&mut self, t: Matrix);
-    fn get_transformation(&self) -> &Matrix;
-    fn get_inverse_transformation(&self) -> &Matrix;
+    // Whether the shape is or contains the shape with the given id.
+    fn includes(&self, id: &Uuid) -> bool {
+        self.get_id() == id
+    }
+
+    fn set_transformation(&mut self, t: Matrix4);
+    fn get_transformation(&self) -> &Matrix4;
+    fn get_inverse_transformation(&self) -> &Matrix4;
 
     fn set_material(&mut self, mat: Material);
     fn get_material(&self) -> &Material;
@@ -0,0 +1,165 @@
+use crate::linalg::matrix4::Matrix4;
+use crate::linalg::tuple::Tuple;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::shapes::bounds::BoundingBox;
+use crate::shapes::shape::Shape;
+use crate::shapes::triangle::Triangle;
+use crate::world::Intersection;
+
+use uuid::Uuid;
+
+
+// Triangle with a normal per vertex. The normal at a hit is
+// interpolated from the vertex normals using the barycentric
+// coordinates recorded on the intersection.
+#[derive(Clone)]
+pub struct SmoothTriangle {
+    pub triangle: Triangle,
+    pub original_n1: Tuple,
+    pub original_n2: Tuple,
+    pub original_n3: Tuple,
+    pub n1: Tuple,
+    pub n2: Tuple,
+    pub n3: Tuple,
+}
+
+impl SmoothTriangle {
+    // The normals n1, n2 and n3 belong to p1, p2 and p3.
+    pub fn new(
+        p1: Tuple, p2: Tuple, p3: Tuple,
+        [n1, n2, n3]: [Tuple; 3],
+        transformation: Matrix4, material: Material
+    ) -> SmoothTriangle {
+        let mut result = SmoothTriangle {
+            triangle: Triangle::new(p1, p2, p3, transformation, material),
+            original_n1: n1,
+            original_n2: n2,
+            original_n3: n3,
+            n1: n1,
+            n2: n2,
+            n3: n3,
+        };
+        result.update();
+        result
+    }
+
+    pub fn update(&mut self) {
+        // Normals are transformed by the transposed inverse to stay
+        // perpendicular to the surface.
+        let m = self.triangle.inv_transformation.transpose();
+        let transform = |n: &Tuple| {
+            let mut tmp = m * *n;
+            tmp.w = 0.0;
+            tmp.normalize()
+        };
+        self.n1 = transform(&self.original_n1);
+        self.n2 = transform(&self.original_n2);
+        self.n3 = transform(&self.original_n3);
+    }
+}
+
+impl Shape for SmoothTriangle {
+    fn normal_at(&self, p: &Tuple) -> Tuple {
+        self.triangle.normal_at(p)
+    }
+
+    fn normal_at_hit(&self, _p: &Tuple, hit: &Intersection) -> Tuple {
+        (self.n2 * hit.u
+            + self.n3 * hit.v
+            + self.n1 * (1.0 - hit.u - hit.v))
+            .normalize()
+    }
+
+    fn intersect(&self, r: &Ray) -> Vec<Intersection> {
+        let mut result = Vec::new();
+        if let Some((t, u, v)) = self.triangle.intersect_uv(r) {
+            result.push(Intersection::with_uv(t, self, u, v));
+        }
+        result
+    }
+
+    fn uv_coordinates(&self, p: &Tuple) -> Tuple {
+        self.triangle.uv_coordinates(p)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.triangle.bounds()
+    }
+
+    fn get_id(&self) -> &Uuid {
+        &self.triangle.id
+    }
+
+    fn set_transformation(&mut self, t: Matrix4) {
+        self.triangle.set_transformation(t);
+        self.update();
+    }
+
+    fn get_transformation(&self) -> &Matrix4 {
+        &self.triangle.transformation
+    }
+
+    fn get_inverse_transformation(&self) -> &Matrix4 {
+        &self.triangle.inv_transformation
+    }
+
+    fn set_material(&mut self, mat: Material) {
+        self.triangle.material = mat;
+    }
+    fn get_material(&self) -> &Material {
+        &self.triangle.material
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::compute_intersection_data;
+
+    fn default_triangle() -> SmoothTriangle {
+        SmoothTriangle::new(
+            Tuple::point(0.0, 1.0, 0.0),
+            Tuple::point(-1.0, 0.0, 0.0),
+            Tuple::point(1.0, 0.0, 0.0),
+            [
+                Tuple::vector(0.0, 1.0, 0.0),
+                Tuple::vector(-1.0, 0.0, 0.0),
+                Tuple::vector(1.0, 0.0, 0.0),
+            ],
+            Matrix4::identity(),
+            Material::default(),
+        )
+    }
+
+    #[test]
+    fn test_intersect_uv() {
+        let t = default_triangle();
+        let r = Ray::new(-0.2, 0.3, -2.0, 0.0, 0.0, 1.0);
+        let ix = t.intersect(&r);
+        assert_eq!(ix.len(), 1);
+        assert!((ix[0].u - 0.45).abs() < 1e-9);
+        assert!((ix[0].v - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_interpolated_normal() {
+        let t = default_triangle();
+        let r = Ray::new(-0.2, 0.3, -2.0, 0.0, 0.0, 1.0);
+        let mut ix = t.intersect(&r);
+        let n = t.normal_at_hit(&Tuple::point(0.0, 0.0, 0.0), &ix[0]);
+        assert_eq!(n, Tuple::vector(-0.5547, 0.83205, 0.0));
+
+        compute_intersection_data(&mut ix, &r);
+        assert_eq!(ix[0].normalv, Tuple::vector(-0.5547, 0.83205, 0.0));
+    }
+
+    #[test]
+    fn test_transformed_normals() {
+        let mut t = default_triangle();
+        t.set_transformation(Matrix4::rotation_z(90.0));
+        assert_eq!(t.n1, Tuple::vector(-1.0, 0.0, 0.0));
+        assert_eq!(t.n3, Tuple::vector(0.0, 1.0, 0.0));
+    }
+}
@@ -1,8 +1,9 @@
 use crate::color::Color;
-use crate::linalg::matrix::Matrix;
+use crate::linalg::matrix4::Matrix4;
 use crate::linalg::tuple::Tuple;
 use crate::material::Material;
 use crate::ray::Ray;
+use crate::shapes::bounds::BoundingBox;
 use crate::shapes::shape::Shape;
 use crate::utils;
 use crate::world::Intersection;
@@ -13,15 +14,15 @@ use uuid::Uuid;
 #[derive(Clone)]
 pub struct Sphere {
     pub id: Uuid,
-    pub transformation: Matrix,
-    pub inv_transformation: Matrix,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
     pub material: Material,
-    pub inv_transformation_transposed: Matrix
+    pub inv_transformation_transposed: Matrix4
 }
 
 impl Sphere {
     #[allow(dead_code)]
-    pub fn new(transformation: Matrix, material: Material) -> Sphere {
+    pub fn new(transformation: Matrix4, material: Material) -> Sphere {
         let inv = transformation.invert();
         Sphere {
             id: Uuid::new_v4(),
@@ -35,19 +36,19 @@ impl Sphere {
     pub fn default() -> Sphere {
         Sphere {
             id: Uuid::new_v4(),
-            transformation: Matrix::identity(4),
-            inv_transformation: Matrix::identity(4),
+            transformation: Matrix4::identity(),
+            inv_transformation: Matrix4::identity(),
             material: Material::default(),
-            inv_transformation_transposed: Matrix::identity(4).transpose(),
+            inv_transformation_transposed: Matrix4::identity().transpose(),
         }
     }
 }
 
 impl Shape for Sphere {
     fn normal_at(&self, p: &Tuple) -> Tuple {
-        let op = self.inv_transformation.multiply_tuple(p);
-        let on = op.subtract(&utils::P0);
-        let mut wn = self.inv_transformation_transposed.multiply_tuple(&on);
+        let op = self.inv_transformation * *p;
+        let on = op - utils::P0;
+        let mut wn = self.inv_transformation_transposed * on;
         wn.w = 0.0;
         wn.normalize()
     }
@@ -56,7 +57,7 @@ impl Shape for Sphere {
         let mut result = Vec::new();
 
         let ray = r.transform(&self.inv_transformation);
-        let shape_to_ray = ray.origin.subtract(&utils::P0);
+        let shape_to_ray = ray.origin - utils::P0;
         let a = ray.direction.dot(&ray.direction);
         let b = 2.0 * ray.direction.dot(&shape_to_ray);
         let c = shape_to_ray.dot(&shape_to_ray) - 1.0;
@@ -85,19 +86,25 @@ impl Shape for Sphere {
         Tuple::point(u, v, 0.0)
     }
 
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(Tuple::point(-1.0, -1.0, -1.0), Tuple::point(1.0, 1.0, 1.0))
+            .transform(&self.transformation)
+    }
+
     fn get_id(&self) -> &Uuid {
         &self.id
     }
 
-    fn set_transformation(&mut self, t: Matrix) {
+    fn set_transformation(&mut self, t: Matrix4) {
         self.inv_transformation = t.invert();
+        self.inv_transformation_transposed = self.inv_transformation.transpose();
         self.transformation = t;
     }
-    fn get_transformation(&self) -> &Matrix {
+    fn get_transformation(&self) -> &Matrix4 {
         &self.transformation
     }
 
-    fn get_inverse_transformation(&self) -> &Matrix {
+    fn get_inverse_transformation(&self) -> &Matrix4 {
         &self.inv_transformation
     }
 
@@ -112,7 +119,7 @@ impl Shape for Sphere {
 #[allow(dead_code)]
 pub fn get_default_spheres() -> Vec<Sphere> {
     let s1 = Sphere::new(
-        Matrix::identity(4),
+        Matrix4::identity(),
         Material::new(
             Some(Color::new(0.8, 1.0, 0.6)),
             None,
@@ -120,7 +127,7 @@ pub fn get_default_spheres() -> Vec<Sphere> {
         ),
     );
     let s2 = Sphere::new(
-        Matrix::scaling(0.5, 0.5, 0.5),
+        Matrix4::scaling(0.5, 0.5, 0.5),
         Material::default(),
     );
     vec![s1, s2]
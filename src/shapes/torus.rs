@@ -0,0 +1,217 @@
+use crate::linalg::matrix4::Matrix4;
+use crate::linalg::roots::solve_quartic;
+use crate::linalg::tuple::Tuple;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::shapes::bounds::BoundingBox;
+use crate::shapes::shape::Shape;
+use crate::utils::P0;
+use crate::world::Intersection;
+
+use uuid::Uuid;
+
+
+// Torus lying in the xz plane around the y axis. The tube of radius
+// minor_radius is swept along a circle of radius major_radius.
+#[derive(Clone)]
+pub struct Torus {
+    pub id: Uuid,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
+    pub material: Material,
+
+    pub major_radius: f64,
+    pub minor_radius: f64,
+}
+
+impl Torus {
+    #[allow(dead_code)]
+    pub fn new(transformation: Matrix4, material: Material, major_radius: f64, minor_radius: f64) -> Torus {
+        Torus {
+            id: Uuid::new_v4(),
+            inv_transformation: transformation.invert(),
+            transformation: transformation,
+            material: material,
+            major_radius: major_radius,
+            minor_radius: minor_radius,
+        }
+    }
+
+    pub fn default() -> Torus {
+        Torus {
+            id: Uuid::new_v4(),
+            inv_transformation: Matrix4::identity(),
+            transformation: Matrix4::identity(),
+            material: Material::default(),
+            major_radius: 1.0,
+            minor_radius: 0.25,
+        }
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        let outer = self.major_radius + self.minor_radius;
+        BoundingBox::new(
+            Tuple::point(-outer, -self.minor_radius, -outer),
+            Tuple::point(outer, self.minor_radius, outer)
+        )
+    }
+}
+
+impl Shape for Torus {
+    fn normal_at(&self, p: &Tuple) -> Tuple {
+        let op = self.inv_transformation * *p;
+
+        // Gradient of (x² + y² + z² - R² - r²)² - 4R²(r² - y²).
+        let sq_major = self.major_radius * self.major_radius;
+        let sq_minor = self.minor_radius * self.minor_radius;
+        let sum = op.x * op.x + op.y * op.y + op.z * op.z;
+        let on = Tuple::vector(
+            op.x * (sum - sq_major - sq_minor),
+            op.y * (sum + sq_major - sq_minor),
+            op.z * (sum - sq_major - sq_minor),
+        );
+
+        let mut wn = self.inv_transformation.transpose() * on;
+        wn.w = 0.0;
+        wn.normalize()
+    }
+
+    fn intersect(&self, r: &Ray) -> Vec<Intersection> {
+        let mut result = Vec::new();
+
+        let ray = r.transform(&self.inv_transformation);
+        if !self.local_bounds().intersects(&ray, f64::NEG_INFINITY, f64::INFINITY) {
+            return result;
+        }
+
+        // Solve for a unit direction, starting from the point on the
+        // ray closest to the center, which keeps the coefficients of
+        // the quartic small and its roots well conditioned.
+        let scale = ray.direction.magnitude();
+        let d = ray.direction / scale;
+        let t_center = -(ray.origin - P0).dot(&d);
+        let o = ray.origin + d * t_center - P0;
+
+        let sq_major = self.major_radius * self.major_radius;
+        let sq_minor = self.minor_radius * self.minor_radius;
+        let e = o.dot(&o) - sq_major - sq_minor;
+        let f = o.dot(&d);
+        let four_sq_major = 4.0 * sq_major;
+
+        let roots = solve_quartic(
+            1.0,
+            4.0 * f,
+            2.0 * e + 4.0 * f * f + four_sq_major * d.y * d.y,
+            4.0 * f * e + 2.0 * four_sq_major * o.y * d.y,
+            e * e - four_sq_major * (sq_minor - o.y * o.y),
+        );
+
+        for t in roots.into_iter() {
+            result.push(Intersection::new((t + t_center) / scale, self));
+        }
+
+        result
+    }
+
+    fn uv_coordinates(&self, p: &Tuple) -> Tuple {
+        // u goes around the y axis, v around the tube.
+        let theta = p.z.atan2(p.x);
+        let u = theta / (2.0 * std::f64::consts::PI) + 0.5;
+
+        let radial = (p.x * p.x + p.z * p.z).sqrt() - self.major_radius;
+        let phi = p.y.atan2(radial);
+        let v = phi / (2.0 * std::f64::consts::PI) + 0.5;
+
+        Tuple::point(u, v, 0.0)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.local_bounds().transform(&self.transformation)
+    }
+
+    fn get_id(&self) -> &Uuid {
+        &self.id
+    }
+
+    fn set_transformation(&mut self, t: Matrix4) {
+        self.inv_transformation = t.invert();
+        self.transformation = t;
+    }
+    fn get_transformation(&self) -> &Matrix4 {
+        &self.transformation
+    }
+    fn get_inverse_transformation(&self) -> &Matrix4 {
+        &self.inv_transformation
+    }
+
+    fn set_material(&mut self, mat: Material) {
+        self.material = mat;
+    }
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::equal;
+
+    #[test]
+    fn test_intersect() {
+        let t = Torus::default();
+
+        // Through the tube on both sides of the hole.
+        let r = Ray::new(-5.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+        let ts: Vec<f64> = t.intersect(&r).iter().map(|i| i.t).collect();
+        assert_eq!(ts.len(), 4);
+        for (a, e) in ts.iter().zip([3.75, 4.25, 5.75, 6.25].iter()) {
+            assert!(equal(*a, *e), "{:?}", ts);
+        }
+
+        // Through the hole.
+        let r = Ray::new(0.0, 5.0, 0.0, 0.0, -1.0, 0.0);
+        assert_eq!(t.intersect(&r).len(), 0);
+
+        // From the top through the tube.
+        let r = Ray::new(1.0, 5.0, 0.0, 0.0, -1.0, 0.0);
+        let ts: Vec<f64> = t.intersect(&r).iter().map(|i| i.t).collect();
+        assert_eq!(ts.len(), 2);
+        assert!(equal(ts[0], 4.75));
+        assert!(equal(ts[1], 5.25));
+    }
+
+    #[test]
+    fn test_transformed_intersect() {
+        let t = Torus::new(
+            Matrix4::translation(0.0, 0.0, 100.0) * Matrix4::scaling(2.0, 2.0, 2.0),
+            Material::default(),
+            1.0,
+            0.25,
+        );
+        let r = Ray::new(-10.0, 0.0, 100.0, 1.0, 0.0, 0.0);
+        let ts: Vec<f64> = t.intersect(&r).iter().map(|i| i.t).collect();
+        assert_eq!(ts.len(), 4);
+        assert!(equal(ts[0], 7.5));
+        assert!(equal(ts[3], 12.5));
+    }
+
+    #[test]
+    fn test_normal_at() {
+        let t = Torus::default();
+        assert_eq!(t.normal_at(&Tuple::point(1.25, 0.0, 0.0)), Tuple::vector(1.0, 0.0, 0.0));
+        assert_eq!(t.normal_at(&Tuple::point(0.75, 0.0, 0.0)), Tuple::vector(-1.0, 0.0, 0.0));
+        assert_eq!(t.normal_at(&Tuple::point(0.0, 0.25, 1.0)), Tuple::vector(0.0, 1.0, 0.0));
+        assert_eq!(t.normal_at(&Tuple::point(0.0, -0.25, -1.0)), Tuple::vector(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn test_uv_coordinates() {
+        let t = Torus::default();
+        let uv = t.uv_coordinates(&Tuple::point(-1.25, 0.0, 0.0));
+        assert_eq!(uv, Tuple::point(1.0, 0.5, 0.0));
+        let uv = t.uv_coordinates(&Tuple::point(0.0, 0.25, 1.0));
+        assert_eq!(uv, Tuple::point(0.75, 0.75, 0.0));
+    }
+}
@@ -1,7 +1,8 @@
-use crate::linalg::matrix::Matrix;
+use crate::linalg::matrix4::Matrix4;
 use crate::linalg::tuple::Tuple;
 use crate::material::Material;
 use crate::ray::Ray;
+use crate::shapes::bounds::BoundingBox;
 use crate::shapes::shape::Shape;
 use crate::utils::EPSILON;
 use crate::world::Intersection;
@@ -21,13 +22,15 @@ pub struct Triangle {
     pub e1: Tuple,
     pub e2: Tuple,
     pub normal: Tuple,
-    pub transformation: Matrix,
-    pub inv_transformation: Matrix,
+    // Texture coordinates at p1, p2 and p3, e.g. from an OBJ file.
+    pub texture_coordinates: Option<[Tuple; 3]>,
+    pub transformation: Matrix4,
+    pub inv_transformation: Matrix4,
     pub material: Material,
 }
 
 impl Triangle {
-    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple, transformation: Matrix, material: Material) -> Triangle {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple, transformation: Matrix4, material: Material) -> Triangle {
         let mut result = Triangle {
             id: Uuid::new_v4(),
             original_p1: p1,
@@ -39,6 +42,7 @@ impl Triangle {
             e1: p1,
             e2: p1,
             normal: p1,
+            texture_coordinates: None,
             inv_transformation: transformation.invert(),
             transformation: transformation,
             material: material,
@@ -47,74 +51,103 @@ impl Triangle {
         result
     }
 
-    pub fn update(&mut self) {
-        self.p1 = self.transformation.multiply_tuple(&self.original_p1);
-        self.p2 = self.transformation.multiply_tuple(&self.original_p2);
-        self.p3 = self.transformation.multiply_tuple(&self.original_p3);
-
-        self.e1 = self.p2.subtract(&self.p1);
-        self.e2 = self.p3.subtract(&self.p1);
-        self.normal = self.p2.cross(&self.p1).normalize();
-    }
-}
-
-impl Shape for Triangle {
-    fn normal_at(&self, _p: &Tuple) -> Tuple {
-        self.normal.clone()
-    }
-
-    fn intersect(&self, r: &Ray) -> Vec<Intersection> {
-        let mut result = Vec::new();
-
+    // Distance along the ray and barycentric coordinates u/v of the
+    // hit, measured along e1 and e2.
+    pub fn intersect_uv(&self, r: &Ray) -> Option<(f64, f64, f64)> {
         // Ray is parallel to triangle.
         let dir_cross_e2 = r.direction.cross(&self.e2);
         let det = self.e1.dot(&dir_cross_e2);
         if det.abs() < EPSILON {
-            return result;
+            return None;
         }
 
         // Ray misses over p3-p1.
         let f = 1.0 / det;
-        let p1_to_origin = r.origin.subtract(&self.p1);
+        let p1_to_origin = r.origin - self.p1;
         let u = f * p1_to_origin.dot(&dir_cross_e2);
         if u < 0.0 || u > 1.0 {
-            return result;
+            return None;
         }
 
         // Ray misses over p1-p2 or p2-p3.
         let origin_cross_e1 = p1_to_origin.cross(&self.e1);
         let v = f * r.direction.dot(&origin_cross_e1);
         if v < 0.0 || (u + v) > 1.0 {
-            return result;
+            return None;
         }
 
         // Ray hits the triangle.
         let t = f * self.e2.dot(&origin_cross_e1);
-        result.push(Intersection::new(t, self));
+        Some((t, u, v))
+    }
+
+    pub fn update(&mut self) {
+        self.p1 = self.transformation * self.original_p1;
+        self.p2 = self.transformation * self.original_p2;
+        self.p3 = self.transformation * self.original_p3;
 
+        self.e1 = self.p2 - self.p1;
+        self.e2 = self.p3 - self.p1;
+        self.normal = self.e2.cross(&self.e1).normalize();
+    }
+}
+
+impl Shape for Triangle {
+    fn normal_at(&self, _p: &Tuple) -> Tuple {
+        self.normal.clone()
+    }
+
+    fn intersect(&self, r: &Ray) -> Vec<Intersection> {
+        let mut result = Vec::new();
+        if let Some((t, u, v)) = self.intersect_uv(r) {
+            result.push(Intersection::with_uv(t, self, u, v));
+        }
         result
     }
 
     fn uv_coordinates(&self, p: &Tuple) -> Tuple {
-        // TODO
-        Tuple::point(p.x, p.y, p.z)
+        let [t1, t2, t3] = match self.texture_coordinates {
+            Some(t) => t,
+            // TODO
+            None => return Tuple::point(p.x, p.y, p.z),
+        };
+
+        // Barycentric coordinates of the point in object space, the
+        // texture coordinates are interpolated with the same weights.
+        let e1 = self.original_p2 - self.original_p1;
+        let e2 = self.original_p3 - self.original_p1;
+        let w = *p - self.original_p1;
+        let (d11, d12, d22) = (e1.dot(&e1), e1.dot(&e2), e2.dot(&e2));
+        let (dw1, dw2) = (w.dot(&e1), w.dot(&e2));
+        let denominator = d11 * d22 - d12 * d12;
+        let u = (d22 * dw1 - d12 * dw2) / denominator;
+        let v = (d11 * dw2 - d12 * dw1) / denominator;
+        t1 * (1.0 - u - v) + t2 * u + t3 * v
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut result = BoundingBox::empty();
+        result.add_point(&self.p1);
+        result.add_point(&self.p2);
+        result.add_point(&self.p3);
+        result
     }
 
     fn get_id(&self) -> &Uuid {
         &self.id
     }
 
-    fn set_transformation(&mut self, t: Matrix) {
+    fn set_transformation(&mut self, t: Matrix4) {
         self.inv_transformation = t.invert();
         self.transformation = t;
         self.update();
     }
 
-    fn get_transformation(&self) -> &Matrix {
+    fn get_transformation(&self) -> &Matrix4 {
         &self.transformation
     }
 
-    fn get_inverse_transformation(&self) -> &Matrix {
+    fn get_inverse_transformation(&self) -> &Matrix4 {
         &self.inv_transformation
     }
 
@@ -138,7 +171,7 @@ mod tests {
             Tuple::point(0.0, 1.0, 0.0),
             Tuple::point(-1.0, 0.0, 0.0),
             Tuple::point(1.0, 0.0, 0.0),
-            Matrix::identity(4),
+            Matrix4::identity(),
             Material::default(),
         );
 
@@ -156,33 +189,37 @@ mod tests {
             Tuple::point(0.0, 1.0, 0.0),
             Tuple::point(-1.0, 0.0, 0.0),
             Tuple::point(1.0, 0.0, 0.0),
-            Matrix::identity(4),
+            Matrix4::identity(),
             Material::default(),
         );
         let r = Ray {
             origin: Tuple::point(0.0, -1.0, -2.0),
-            direction: Tuple::vector(0.0, 1.0, 0.0)
+            direction: Tuple::vector(0.0, 1.0, 0.0),
+            time: 0.0,
         };
         let ix = t.intersect(&r);
         assert_eq!(ix.len(), 0);
 
         let r = Ray {
             origin: Tuple::point(1.0, 1.0, -2.0),
-            direction: Tuple::vector(0.0, 0.0, 1.0)
+            direction: Tuple::vector(0.0, 0.0, 1.0),
+            time: 0.0,
         };
         let ix = t.intersect(&r);
         assert_eq!(ix.len(), 0);
 
         let r = Ray {
             origin: Tuple::point(-1.0, 1.0, -2.0),
-            direction: Tuple::vector(0.0, 0.0, 1.0)
+            direction: Tuple::vector(0.0, 0.0, 1.0),
+            time: 0.0,
         };
         let ix = t.intersect(&r);
         assert_eq!(ix.len(), 0);
 
         let r = Ray {
             origin: Tuple::point(1.0, -1.0, -2.0),
-            direction: Tuple::vector(0.0, 0.0, 1.0)
+            direction: Tuple::vector(0.0, 0.0, 1.0),
+            time: 0.0,
         };
         let ix = t.intersect(&r);
         assert_eq!(ix.len(), 0);
@@ -194,12 +231,13 @@ mod tests {
             Tuple::point(0.0, 1.0, 0.0),
             Tuple::point(-1.0, 0.0, 0.0),
             Tuple::point(1.0, 0.0, 0.0),
-            Matrix::identity(4),
+            Matrix4::identity(),
             Material::default(),
         );
         let r = Ray {
             origin: Tuple::point(0.0, 0.5, -2.0),
-            direction: Tuple::vector(0.0, 0.0, 1.0)
+            direction: Tuple::vector(0.0, 0.0, 1.0),
+            time: 0.0,
         };
         let ix = t.intersect(&r);
         assert_eq!(ix.len(), 1);
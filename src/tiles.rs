@@ -0,0 +1,237 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+
+// Order in which tiles are handed out to the render threads.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TileOrder {
+    // Row by row from the top left.
+    Scanline,
+    // Outwards from the centre, where the subject usually is.
+    Spiral,
+    // Along a Hilbert curve, consecutive tiles are neighbours on square
+    // grids of a power of two. Other grids skip the cells of the curve
+    // outside of them, tiles still follow each other closely.
+    Hilbert,
+}
+
+impl TileOrder {
+    pub fn from_name(name: &str) -> TileOrder {
+        match name {
+            "scanline" => TileOrder::Scanline,
+            "spiral" => TileOrder::Spiral,
+            "hilbert" => TileOrder::Hilbert,
+            _ => panic!("Undefined tile order: {:?}!", name),
+        }
+    }
+}
+
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+// Position on a Hilbert curve filling a square of side n, a power of
+// two, to the cell it passes.
+fn hilbert_cell(n: usize, d: usize) -> (usize, usize) {
+    let (mut x, mut y) = (0, 0);
+    let mut t = d;
+    let mut s = 1;
+    while s < n {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
+
+// Cells of a columns x rows grid in the given order.
+fn cell_order(columns: usize, rows: usize, order: TileOrder) -> Vec<(usize, usize)> {
+    let count = columns * rows;
+    if count == 0 {
+        return vec![];
+    }
+    match order {
+        TileOrder::Scanline => (0..count).map(|i| (i % columns, i / columns)).collect(),
+        TileOrder::Spiral => {
+            // Walk a square spiral around the centre cell, with legs of
+            // 1, 1, 2, 2, 3, 3, ... cells, until all cells are found.
+            let mut result = Vec::with_capacity(count);
+            let (mut x, mut y) = (((columns - 1) / 2) as i64, ((rows - 1) / 2) as i64);
+            let directions = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+            let mut leg = 1;
+            let mut direction = 0;
+            while result.len() < count {
+                for _ in 0..2 {
+                    for _ in 0..leg {
+                        if x >= 0 && y >= 0 && (x as usize) < columns && (y as usize) < rows {
+                            result.push((x as usize, y as usize));
+                        }
+                        x += directions[direction].0;
+                        y += directions[direction].1;
+                    }
+                    direction = (direction + 1) % 4;
+                }
+                leg += 1;
+            }
+            result
+        },
+        TileOrder::Hilbert => {
+            let n = columns.max(rows).next_power_of_two();
+            (0..n * n).map(|d| hilbert_cell(n, d))
+                .filter(|&(x, y)| x < columns && y < rows)
+                .collect()
+        },
+    }
+}
+
+// Tiles covering the image, the ones at the right and bottom edge may
+// be smaller.
+pub fn tiles(width: usize, height: usize, tile_size: usize, order: TileOrder) -> Vec<Tile> {
+    let size = tile_size.max(1);
+    let columns = width.div_ceil(size);
+    let rows = height.div_ceil(size);
+    cell_order(columns, rows, order).into_iter().map(|(cx, cy)| Tile {
+        x: cx * size,
+        y: cy * size,
+        width: size.min(width - cx * size),
+        height: size.min(height - cy * size),
+    }).collect()
+}
+
+// Calls f for every pixel, rendering the tiles in order on all threads
+// of the rayon pool. Every thread takes the next tile as soon as it is
+// done with one, so an expensive part of the image holds up no more
+// than one thread at a time. Calls done after every finished tile, on
+// the thread that rendered it.
+// Returns the image row by row.
+pub fn render<T, F, G>(width: usize, height: usize, tile_size: usize, order: TileOrder, f: F, done: G) -> Vec<T>
+where
+    T: Clone + Send,
+    F: Fn(usize, usize) -> T + Sync,
+    G: Fn() + Sync,
+{
+    let tiles = tiles(width, height, tile_size, order);
+    let framebuffer = Mutex::new(vec![None; width * height]);
+    let next = AtomicUsize::new(0);
+
+    rayon::scope(|s| {
+        for _ in 0..rayon::current_num_threads() {
+            s.spawn(|_| {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    if i >= tiles.len() {
+                        break;
+                    }
+                    let tile = tiles[i];
+                    let mut pixels = Vec::with_capacity(tile.width * tile.height);
+                    for y in tile.y..tile.y + tile.height {
+                        for x in tile.x..tile.x + tile.width {
+                            pixels.push(Some(f(x, y)));
+                        }
+                    }
+
+                    let mut framebuffer = framebuffer.lock().unwrap();
+                    for (row, chunk) in pixels.chunks(tile.width).enumerate() {
+                        let start = (tile.y + row) * width + tile.x;
+                        framebuffer[start..start + tile.width].clone_from_slice(chunk);
+                    }
+                    drop(framebuffer);
+                    done();
+                }
+            });
+        }
+    });
+    framebuffer.into_inner().unwrap().into_iter().map(|p| p.unwrap()).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tiles() {
+        for order in [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert].iter() {
+            let tiles = tiles(37, 21, 8, *order);
+            assert_eq!(tiles.len(), 5 * 3);
+
+            // Every pixel is in exactly one tile.
+            let mut covered = vec![0; 37 * 21];
+            for t in tiles.iter() {
+                for y in t.y..t.y + t.height {
+                    for x in t.x..t.x + t.width {
+                        covered[y * 37 + x] += 1;
+                    }
+                }
+            }
+            assert!(covered.iter().all(|&c| c == 1));
+        }
+
+        let t = tiles(37, 21, 8, TileOrder::Scanline);
+        assert_eq!(t[0], Tile { x: 0, y: 0, width: 8, height: 8 });
+        assert_eq!(t[4], Tile { x: 32, y: 0, width: 5, height: 8 });
+        assert_eq!(t[5], Tile { x: 0, y: 8, width: 8, height: 8 });
+
+        let t = tiles(37, 21, 8, TileOrder::Spiral);
+        assert_eq!((t[0].x, t[0].y), (16, 8));
+        assert_eq!((t[1].x, t[1].y), (24, 8));
+    }
+
+    #[test]
+    fn test_hilbert() {
+        let cells = cell_order(8, 8, TileOrder::Hilbert);
+        assert_eq!(cells[0], (0, 0));
+        assert_eq!(cells[63], (7, 0));
+        for w in cells.windows(2) {
+            let d = (w[0].0 as i64 - w[1].0 as i64).abs() + (w[0].1 as i64 - w[1].1 as i64).abs();
+            assert_eq!(d, 1);
+        }
+
+        // Other grids get every cell once, though not always next to
+        // the one before.
+        let mut cells = cell_order(5, 3, TileOrder::Hilbert);
+        assert_eq!(cells.len(), 15);
+        cells.sort();
+        cells.dedup();
+        assert_eq!(cells.len(), 15);
+    }
+
+    #[test]
+    fn test_empty() {
+        for order in [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert].iter() {
+            assert!(tiles(0, 10, 4, *order).is_empty());
+            assert!(tiles(10, 0, 4, *order).is_empty());
+            assert!(render(0, 0, 4, *order, |x, y| (x, y), || ()).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        for order in [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert].iter() {
+            let finished = AtomicUsize::new(0);
+            let image = render(13, 7, 4, *order, |x, y| (x, y), || {
+                finished.fetch_add(1, Ordering::Relaxed);
+            });
+            assert_eq!(finished.into_inner(), 4 * 2);
+            assert_eq!(image.len(), 13 * 7);
+            for (i, p) in image.iter().enumerate() {
+                assert_eq!(*p, (i % 13, i / 13));
+            }
+        }
+    }
+}
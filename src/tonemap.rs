@@ -0,0 +1,78 @@
+use crate::color::Color;
+
+
+// Operators mapping unbounded linear radiance into [0, 1] for output.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ToneMapping {
+    // Cut off everything above 1.0, the behaviour of the old clamped
+    // color pipeline.
+    Clamp,
+    // x / (1 + x) per channel, compresses highlights but never reaches
+    // pure white.
+    Reinhard,
+    // Narkowicz' fit of the ACES filmic curve, a slight toe in the
+    // shadows and a soft shoulder in the highlights.
+    Aces,
+}
+
+impl ToneMapping {
+    pub fn from_name(name: &str) -> ToneMapping {
+        match name {
+            "clamp" => ToneMapping::Clamp,
+            "reinhard" => ToneMapping::Reinhard,
+            "aces" => ToneMapping::Aces,
+            _ => panic!("Undefined tone mapping: {:?}!", name),
+        }
+    }
+
+    // Exposure is given in stops, every step doubles the brightness
+    // before the curve is applied.
+    pub fn apply(&self, c: &Color, exposure: f64) -> Color {
+        let scaled = *c * 2.0_f64.powf(exposure);
+        let map = |x: f64| {
+            let x = x.max(0.0);
+            let result = match self {
+                ToneMapping::Clamp => x,
+                ToneMapping::Reinhard => x / (1.0 + x),
+                ToneMapping::Aces => (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14),
+            };
+            result.min(1.0)
+        };
+        Color::new(map(scaled.r), map(scaled.g), map(scaled.b))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clamp() {
+        let c = Color::new(0.5, 1.5, -0.2);
+        assert_eq!(ToneMapping::Clamp.apply(&c, 0.0), Color::new(0.5, 1.0, 0.0));
+        assert_eq!(ToneMapping::Clamp.apply(&c, -1.0), Color::new(0.25, 0.75, 0.0));
+    }
+
+    #[test]
+    fn test_reinhard() {
+        let c = Color::new(1.0, 3.0, 0.0);
+        assert_eq!(ToneMapping::Reinhard.apply(&c, 0.0), Color::new(0.5, 0.75, 0.0));
+        assert_eq!(ToneMapping::Reinhard.apply(&c, 1.0), Color::new(0.66667, 0.85714, 0.0));
+    }
+
+    #[test]
+    fn test_aces() {
+        let m = ToneMapping::Aces;
+        assert_eq!(m.apply(&Color::black(), 0.0), Color::black());
+        assert_eq!(m.apply(&Color::new(100.0, 100.0, 100.0), 0.0), Color::white());
+
+        // Monotonic and within [0, 1].
+        let mut last = 0.0;
+        for i in 1..50 {
+            let v = m.apply(&Color::new(i as f64 * 0.1, 0.0, 0.0), 0.0).r;
+            assert!(v > last && v <= 1.0);
+            last = v;
+        }
+    }
+}
@@ -1,22 +1,52 @@
+use crate::bvh::Bvh;
 use crate::color::Color;
 use crate::light::PointLight;
+use crate::linalg::matrix4::Matrix4;
 use crate::linalg::tuple::Tuple;
 use crate::ray::{Ray, reflect};
 use crate::shapes::shape::Shape;
 use crate::utils::EPSILON;
 
+use std::cell::Cell;
+
 use uuid::Uuid;
 
 
 static MAX_REC_DEPTH: u8 = 10;
 
+thread_local! {
+    // Rays traced by this thread, camera and shadow rays alike. Every
+    // thread counts its own, a shared counter would be touched by all
+    // of them for every ray.
+    static RAYS: Cell<usize> = const { Cell::new(0) };
+}
+
+// Rays traced by the calling thread since the last call.
+pub fn take_ray_count() -> usize {
+    RAYS.with(|r| r.replace(0))
+}
+
+fn count_ray() {
+    RAYS.with(|r| r.set(r.get() + 1));
+}
+
 
 pub struct World {
     pub objects: Vec<Box<dyn Shape + Sync>>,
     pub lights: Vec<PointLight>,
+    pub bvh: Option<Bvh>,
 }
 
 impl World {
+    pub fn new(objects: Vec<Box<dyn Shape + Sync>>, lights: Vec<PointLight>) -> World {
+        let bvh = Bvh::new(&objects);
+        World {
+            objects: objects,
+            lights: lights,
+            bvh: Some(bvh),
+        }
+    }
+
     #[allow(dead_code)]
     pub fn default() -> World {
         let light = PointLight {
@@ -27,14 +57,25 @@ impl World {
         World {
             objects: vec![],
             lights: vec![light],
+            bvh: None,
+        }
+    }
+
+    // Indices of the objects that might be hit by the ray between
+    // t_min and t_max. Without a BVH every object is a candidate.
+    fn candidates(&self, ray: &Ray, t_min: f64, t_max: f64) -> Vec<usize> {
+        match &self.bvh {
+            Some(bvh) => bvh.candidates(ray, t_min, t_max),
+            None => (0..self.objects.len()).collect(),
         }
     }
 
     pub fn intersect<'a>(&'a self, ray: &'a Ray) -> Vec<Intersection<'a>> {
+        count_ray();
         let mut result = Vec::new();
 
-        for obj in self.objects.iter() {
-            let mut intersections = obj.intersect(&ray);
+        for i in self.candidates(ray, f64::NEG_INFINITY, f64::INFINITY) {
+            let mut intersections = self.objects[i].intersect(&ray);
             result.append(&mut intersections);
         }
         result.sort_by(|a, b| a.t.partial_cmp(&b.t).unwrap());
@@ -49,15 +90,14 @@ impl World {
         let mut result = Color::black();
 
         for light in self.lights.iter() {
-            let is_shadowed = self.is_shadowed(&int.over_point, &light);
-            result = result.add(
-                &light.lighting(
-                    int.object,
-                    &int.point,
-                    is_shadowed,
-                    &int.eyev,
-                    &int.normalv
-                )
+            let is_shadowed = self.is_shadowed(&int.over_point, &light, int.time);
+            result += light.lighting(
+                int.object,
+                &int.point,
+                &int.reference_point,
+                is_shadowed,
+                &int.eyev,
+                &int.normalv
             );
         }
 
@@ -65,6 +105,12 @@ impl World {
     }
 
     pub fn color_at(&self, ray: &Ray, rec_depth: u8) -> Color {
+        self.trace(ray, rec_depth).0
+    }
+
+    // Color seen along the ray, together with the id of the object it
+    // hits first.
+    pub fn trace(&self, ray: &Ray, rec_depth: u8) -> (Color, Option<Uuid>) {
         // Find the first object the ray hits.
         let intersections = self.intersect(ray);
         let hit = hit(&intersections);
@@ -95,18 +141,17 @@ impl World {
                 let mat = int.object.get_material();
                 if mat.reflective > 0.0 && mat.transparency > 0.0 {
                     let reflectance = schlick(&int);
-                    reflect_color.multiply(reflectance)
-                        .add(&refract_color.multiply(1.0 - reflectance))
+                    reflect_color * reflectance + refract_color * (1.0 - reflectance)
                 } else {
-                    reflect_color.add(&refract_color)
+                    reflect_color + refract_color
                 }
             },
-            None => reflect_color.add(&refract_color),
+            None => reflect_color + refract_color,
         };
 
         // Return final value.
-        let result = color.add(&added_color);
-        result
+        let result = color + added_color;
+        (result, hit.map(|i| *i.object.get_id()))
     }
 
     pub fn reflected_color_at(&self, hit: &Intersection, rec_depth: u8) -> Color {
@@ -116,8 +161,9 @@ impl World {
             let reflect_ray = Ray {
                 origin: hit.over_point,
                 direction: hit.reflectv,
+                time: hit.time,
             };
-            result = self.color_at(&reflect_ray, rec_depth + 1).multiply(factor);
+            result = self.color_at(&reflect_ray, rec_depth + 1) * factor;
         }
         result
     }
@@ -132,36 +178,38 @@ impl World {
             if sin2_t < 1.0 {
                 // No internal reflection
                 let cos_t = (1.0 - sin2_t).sqrt();
-                let direction = hit.normalv
-                    .multiply(n_ratio * cos_i - cos_t)
-                    .subtract(&hit.eyev.multiply(n_ratio));
+                let direction = hit.normalv * (n_ratio * cos_i - cos_t) - hit.eyev * n_ratio;
                 let refract_ray = Ray {
                     origin: hit.under_point,
                     direction: direction,
+                    time: hit.time,
                 };
                 let factor = hit.object.get_material().transparency;
-                result = self.color_at(&refract_ray, rec_depth + 1).multiply(factor);
+                result = self.color_at(&refract_ray, rec_depth + 1) * factor;
             }
         }
 
         result
     }
 
-    pub fn is_shadowed(&self, point: &Tuple, light: &PointLight) -> bool {
-        let v = light.position.subtract(point);
+    pub fn is_shadowed(&self, point: &Tuple, light: &PointLight, time: f64) -> bool {
+        let v = light.position - *point;
         let distance = v.magnitude();
         let direction = v.normalize();
 
         let ray = Ray {
             origin: *point,
             direction: direction,
+            time: time,
         };
-        let intersections = self.intersect(&ray);
-        let h = hit(&intersections);
-        match h {
-            Some(x) => x.t < distance,
-            None => false
+        count_ray();
+        for i in self.candidates(&ray, 0.0, distance) {
+            let intersections = self.objects[i].intersect(&ray);
+            if intersections.iter().any(|x| x.t >= 0.0 && x.t < distance) {
+                return true;
+            }
         }
+        false
     }
 }
 
@@ -169,14 +217,29 @@ impl World {
 pub fn compute_intersection_data(result: &mut Vec<Intersection>, r: &Ray) -> () {
     for i in result.iter_mut() {
         i.point = r.position(i.t);
-        i.normalv = i.object.normal_at(&i.point);
-        i.eyev = r.direction.negate();
+        i.time = r.time;
+        let hit = *i;
+        i.normalv = match i.inv_motion {
+            // Moving objects compute their normals where they were
+            // built, which are then moved along with them.
+            Some(inverse) => {
+                i.reference_point = inverse * i.point;
+                let mut n = inverse.transpose() * i.object.normal_at_hit(&i.reference_point, &hit);
+                n.w = 0.0;
+                n.normalize()
+            },
+            None => {
+                i.reference_point = i.point;
+                i.object.normal_at_hit(&i.point, &hit)
+            },
+        };
+        i.eyev = -r.direction;
         i.inside = i.normalv.dot(&i.eyev) < 0.0;
         if i.inside {
-            i.normalv = i.normalv.negate();
+            i.normalv = -i.normalv;
         }
-        i.over_point = i.point.add(&i.normalv.multiply(EPSILON));
-        i.under_point = i.point.subtract(&i.normalv.multiply(EPSILON));
+        i.over_point = i.point + i.normalv * EPSILON;
+        i.under_point = i.point - i.normalv * EPSILON;
         i.reflectv = reflect(&r.direction, &i.normalv);
     }
 }
@@ -187,22 +250,22 @@ pub fn compute_n1n2(result: &mut Vec<Intersection>) -> () {
         if containers.len() == 0 {
             result[i].n1 = 1.0;
         } else {
-            let pos = result.iter().position(|&x| x.object.get_id() == containers[containers.len() - 1]).unwrap();
-            result[i].n1 = result[pos].object.get_material().refractive_index;
+            let pos = result.iter().position(|&x| x.container.get_id() == containers[containers.len() - 1]).unwrap();
+            result[i].n1 = result[pos].container.get_material().refractive_index;
         }
 
-        if containers.contains(&result[i].object.get_id()) {
-            let pos = containers.iter().position(|&x| x == result[i].object.get_id()).unwrap();
+        if containers.contains(&result[i].container.get_id()) {
+            let pos = containers.iter().position(|&x| x == result[i].container.get_id()).unwrap();
             containers.remove(pos);
         } else {
-            containers.push(result[i].object.get_id());
+            containers.push(result[i].container.get_id());
         }
 
         if containers.len() == 0 {
             result[i].n2 = 1.0;
         } else {
-            let pos = result.iter().position(|&x| x.object.get_id() == containers[containers.len() - 1]).unwrap();
-            result[i].n2 = result[pos].object.get_material().refractive_index;
+            let pos = result.iter().position(|&x| x.container.get_id() == containers[containers.len() - 1]).unwrap();
+            result[i].n2 = result[pos].container.get_material().refractive_index;
         }
     }
 }
@@ -227,6 +290,9 @@ pub fn schlick(hit: &Intersection) -> f64 {
 pub struct Intersection<'a> {
     pub t: f64,
     pub object: &'a (dyn Shape + Sync),
+    // Volume that is entered or left at this intersection, which is
+    // the object itself unless it is part of a CSG shape.
+    pub container: &'a (dyn Shape + Sync),
     pub point: Tuple,
     pub over_point: Tuple,
     pub under_point: Tuple,
@@ -236,6 +302,15 @@ pub struct Intersection<'a> {
     pub inside: bool,
     pub n1: f64,
     pub n2: f64,
+    pub u: f64,
+    pub v: f64,
+    // Time of the ray, and for moving objects the transformation from
+    // their position at that time back to the one they were built in.
+    pub time: f64,
+    pub inv_motion: Option<Matrix4>,
+    // Hit point on the object where it was built, the same as point
+    // unless the object moves.
+    pub reference_point: Tuple,
 }
 
 impl<'a> Intersection<'a> {
@@ -244,6 +319,7 @@ impl<'a> Intersection<'a> {
         Intersection {
             t: t,
             object: object,
+            container: object,
             point: p,
             over_point: p,
             under_point: p,
@@ -252,9 +328,23 @@ impl<'a> Intersection<'a> {
             reflectv: p,
             inside: false,
             n1: 1.0,
-            n2: 1.0
+            n2: 1.0,
+            u: 0.0,
+            v: 0.0,
+            time: 0.0,
+            inv_motion: None,
+            reference_point: p,
         }
     }
+
+    // Intersection with the surface coordinates u/v of the hit, used
+    // by triangles to interpolate along their edges.
+    pub fn with_uv(t: f64, object: &(dyn Shape + Sync), u: f64, v: f64) -> Intersection {
+        let mut result = Intersection::new(t, object);
+        result.u = u;
+        result.v = v;
+        result
+    }
 }
 
 pub fn hit<'a>(intersections: &'a Vec<Intersection>) -> Option<Intersection<'a>> {
@@ -274,8 +364,8 @@ mod tests {
     use super::*;
     use crate::patterns::test::TestPattern;
     use crate::material::Material;
-    use crate::linalg::matrix::Matrix;
-    use crate::shapes::{plane::Plane, sphere::Sphere, sphere::get_default_spheres, sphere::get_glass_sphere};
+    use crate::linalg::matrix4::Matrix4;
+    use crate::shapes::{plane::Plane, sphere::Sphere, sphere::get_default_spheres, sphere::get_glass_sphere, triangle::Triangle};
     use crate::utils::equal;
 
     #[test]
@@ -287,6 +377,7 @@ mod tests {
         let r = Ray {
             origin: Tuple::point(0.0, 0.0, -5.0),
             direction: Tuple::vector(0.0, 0.0, 1.0),
+            time: 0.0,
         };
         let i = w.intersect(&r);
 
@@ -305,6 +396,7 @@ mod tests {
         let r = Ray {
             origin: Tuple::point(0.0, 0.0, -5.0),
             direction: Tuple::vector(0.0, 0.0, 1.0),
+            time: 0.0,
         };
         let x = w.intersect(&r);
         assert_eq!(x[0].t, 4.0);
@@ -316,6 +408,7 @@ mod tests {
         let r = Ray {
             origin: Tuple::point(0.0, 0.0, 0.0),
             direction: Tuple::vector(0.0, 0.0, 1.0),
+            time: 0.0,
         };
         let x = w.intersect(&r);
         assert_eq!(x[3].t, 1.0);
@@ -334,6 +427,7 @@ mod tests {
         let r = Ray {
             origin: Tuple::point(0.0, 0.0, -5.0),
             direction: Tuple::vector(0.0, 0.0, 1.0),
+            time: 0.0,
         };
         let c = w.color_at(&r, 0);
         assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
@@ -345,6 +439,7 @@ mod tests {
         let r = Ray {
             origin: Tuple::point(0.0, 0.0, 0.0),
             direction: Tuple::vector(0.0, 0.0, 1.0),
+            time: 0.0,
         };
         let c = w.color_at(&r, 0);
         assert_eq!(c, Color::new(0.90498, 0.90498, 0.90498));
@@ -356,6 +451,7 @@ mod tests {
         let r = Ray {
             origin: Tuple::point(0.0, 0.0, -5.0),
             direction: Tuple::vector(0.0, 1.0, 0.0),
+            time: 0.0,
         };
         let c = w.color_at(&r, 0);
         assert_eq!(c, Color::black());
@@ -364,7 +460,7 @@ mod tests {
     #[test]
     fn test_n1n2() {
         let a = Sphere::new(
-            Matrix::scaling(2.0, 2.0, 2.0),
+            Matrix4::scaling(2.0, 2.0, 2.0),
             Material::new(
                 Some(Color::white()),
                 None,
@@ -373,7 +469,7 @@ mod tests {
         );
 
         let b = Sphere::new(
-            Matrix::translation(0.0, 0.0, -0.25),
+            Matrix4::translation(0.0, 0.0, -0.25),
             Material::new(
                 Some(Color::white()),
                 None,
@@ -382,7 +478,7 @@ mod tests {
         );
 
         let c = Sphere::new(
-            Matrix::translation(0.0, 0.0, 0.25),
+            Matrix4::translation(0.0, 0.0, 0.25),
             Material::new(
                 Some(Color::white()),
                 None,
@@ -395,17 +491,18 @@ mod tests {
             intensity: Color::white(),
         };
 
-        let world = World {
-            objects: vec![
+        let world = World::new(
+            vec![
                 Box::new(a),
                 Box::new(b),
                 Box::new(c),
             ],
-            lights: vec![light],
-        };
+            vec![light],
+        );
         let ray = Ray {
             origin: Tuple::point(0.0, 0.0, -4.0),
             direction: Tuple::vector(0.0, 0.0, 1.0),
+            time: 0.0,
         };
 
         let intersections = world.intersect(&ray);
@@ -429,10 +526,11 @@ mod tests {
         let ray = Ray {
             origin: Tuple::point(0.0, 0.0, -5.0),
             direction: Tuple::vector(0.0, 0.0, 1.0),
+            time: 0.0,
         };
 
         let sphere = Sphere::new(
-            Matrix::translation(0.0, 0.0, 1.0),
+            Matrix4::translation(0.0, 0.0, 1.0),
             Material::new(
                 Some(Color::white()),
                 None,
@@ -458,6 +556,7 @@ mod tests {
         let ray = Ray {
             origin: Tuple::point(0.0, 0.0, -5.0),
             direction: Tuple::vector(0.0, 0.0, 1.0),
+            time: 0.0,
         };
         let intersections = shape.intersect(&ray);
         for int in intersections.iter() {
@@ -471,7 +570,7 @@ mod tests {
         let mut world = World::default();
 
         let s1 = Sphere::new(
-            Matrix::identity(4),
+            Matrix4::identity(),
             Material::new(
                 Some(Color::new(0.8, 1.0, 0.6)),
                 None,
@@ -479,7 +578,7 @@ mod tests {
             ),
         );
         let s2 = Sphere::new(
-            Matrix::scaling(0.5, 0.5, 0.5),
+            Matrix4::scaling(0.5, 0.5, 0.5),
             Material::default(),
         );
         world.objects = vec![Box::new(s1.clone()), Box::new(s2.clone())];
@@ -487,6 +586,7 @@ mod tests {
         let ray = Ray {
             origin: Tuple::point(0.0, 0.0, -5.0),
             direction: Tuple::vector(0.0, 0.0, 1.0),
+            time: 0.0,
         };
         let intersections = s1.intersect(&ray);
         for int in intersections.iter() {
@@ -500,7 +600,7 @@ mod tests {
         let mut world = World::default();
 
         let s1 = Sphere::new(
-            Matrix::identity(4),
+            Matrix4::identity(),
             Material::new(
                 Some(Color::new(0.8, 1.0, 0.6)),
                 None,
@@ -508,7 +608,7 @@ mod tests {
             ),
         );
         let s2 = Sphere::new(
-            Matrix::scaling(0.5, 0.5, 0.5),
+            Matrix4::scaling(0.5, 0.5, 0.5),
             Material::default(),
         );
         world.objects = vec![Box::new(s1), Box::new(s2)];
@@ -516,6 +616,7 @@ mod tests {
         let ray = Ray {
             origin: Tuple::point(0.0, 0.0, 2.0f64.sqrt() / 2.0),
             direction: Tuple::vector(0.0, 1.0, 0.0),
+            time: 0.0,
         };
         let intersections = world.intersect(&ray);
         let color = world.refracted_color_at(&intersections[1], 0);
@@ -526,9 +627,9 @@ mod tests {
     fn test_refracted_color_for_real() {
         let mut world = World::default();
 
-        let pattern = TestPattern::new(Matrix::identity(4));
+        let pattern = TestPattern::new(Matrix4::identity());
         let s1 = Sphere::new(
-            Matrix::identity(4),
+            Matrix4::identity(),
             Material::new(
                 None,
                 Some(Box::new(pattern)),
@@ -536,7 +637,7 @@ mod tests {
             ),
         );
         let s2 = Sphere::new(
-            Matrix::scaling(0.5, 0.5, 0.5),
+            Matrix4::scaling(0.5, 0.5, 0.5),
             Material::new(
                 Some(Color::white()),
                 None,
@@ -549,6 +650,7 @@ mod tests {
         let ray = Ray {
             origin: Tuple::point(0.0, 0.0, 0.1),
             direction: Tuple::vector(0.0, 1.0, 0.0),
+            time: 0.0,
         };
         let intersections = world.intersect(&ray);
         let n = 2;
@@ -563,7 +665,7 @@ mod tests {
         let mut world = World::default();
 
         let s1 = Sphere::new(
-            Matrix::identity(4),
+            Matrix4::identity(),
             Material::new(
                 Some(Color::new(0.8, 1.0, 0.6)),
                 None,
@@ -571,7 +673,7 @@ mod tests {
             ),
         );
         let s2 = Sphere::new(
-            Matrix::scaling(0.5, 0.5, 0.5),
+            Matrix4::scaling(0.5, 0.5, 0.5),
             Material::default(),
         );
 
@@ -579,7 +681,7 @@ mod tests {
         floor_material.transparency = 0.5;
         floor_material.refractive_index = 1.5;
         let floor = Plane::new(
-            Matrix::translation(0.0, -1.0, 0.0),
+            Matrix4::translation(0.0, -1.0, 0.0),
             floor_material
         );
 
@@ -587,7 +689,7 @@ mod tests {
         ball_material.color = Some(Color::new(1.0, 0.0, 0.0));
         ball_material.ambient = 0.5;
         let ball = Sphere::new(
-            Matrix::translation(0.0, -3.5, -0.5),
+            Matrix4::translation(0.0, -3.5, -0.5),
             ball_material
         );
 
@@ -596,6 +698,7 @@ mod tests {
         let ray = Ray {
             origin: Tuple::point(0.0, 0.0, -3.0),
             direction: Tuple::vector(0.0, -2.0f64.sqrt() / 2.0, 2.0f64.sqrt() / 2.0),
+            time: 0.0,
         };
         let color = world.color_at(&ray, 0);
         assert_eq!(color, Color::new(0.93642, 0.68642, 0.68642));
@@ -607,6 +710,7 @@ mod tests {
         let ray = Ray {
             origin: Tuple::point(0.0, 0.0, 2.0f64.sqrt() / 2.0),
             direction: Tuple::vector(0.0, 1.0, 0.0),
+            time: 0.0,
         };
         let mut intersections = &mut glas.intersect(&ray);
         compute_intersection_data(&mut intersections, &ray);
@@ -622,6 +726,7 @@ mod tests {
         let ray = Ray {
             origin: Tuple::point(0.0, 0.0, 0.0),
             direction: Tuple::vector(0.0, 1.0, 0.0),
+            time: 0.0,
         };
         let mut intersections = &mut glas.intersect(&ray);
         compute_n1n2(&mut intersections);
@@ -635,6 +740,7 @@ mod tests {
         let ray = Ray {
             origin: Tuple::point(0.0, 0.99, -2.0),
             direction: Tuple::vector(0.0, 0.0, 1.0),
+            time: 0.0,
         };
         let mut intersections = &mut glas.intersect(&ray);
         compute_intersection_data(&mut intersections, &ray);
@@ -648,6 +754,7 @@ mod tests {
         let ray = Ray {
             origin: Tuple::point(0.0, 0.0, -3.0),
             direction: Tuple::vector(0.0, -2.0f64.sqrt() / 2.0, 2.0f64.sqrt() / 2.0),
+            time: 0.0,
         };
 
         let mut floor_material = Material::default();
@@ -656,8 +763,8 @@ mod tests {
         floor_material.refractive_index = 1.5;
         let floor = Plane {
             id: Uuid::new_v4(),
-            transformation: Matrix::translation(0.0, -1.0, 0.0),
-            inv_transformation: Matrix::translation(0.0, -1.0, 0.0).invert(),
+            transformation: Matrix4::translation(0.0, -1.0, 0.0),
+            inv_transformation: Matrix4::translation(0.0, -1.0, 0.0).invert(),
             material: floor_material,
         };
 
@@ -666,9 +773,9 @@ mod tests {
         ball_material.ambient = 0.5;
         let ball = Sphere {
             id: Uuid::new_v4(),
-            transformation: Matrix::translation(0.0, -3.5, -0.5),
-            inv_transformation: Matrix::translation(0.0, -3.5, -0.5).invert(),
-            inv_transformation_transposed: Matrix::translation(0.0, -3.5, -0.5).invert().transpose(),
+            transformation: Matrix4::translation(0.0, -3.5, -0.5),
+            inv_transformation: Matrix4::translation(0.0, -3.5, -0.5).invert(),
+            inv_transformation_transposed: Matrix4::translation(0.0, -3.5, -0.5).invert().transpose(),
             material: ball_material,
         };
 
@@ -685,4 +792,41 @@ mod tests {
         let expected = Color::new(0.93391, 0.69643, 0.69243);
         assert_eq!(color, expected);
     }
+
+    fn triangle_grid() -> Vec<Box<dyn Shape + Sync>> {
+        let mut result: Vec<Box<dyn Shape + Sync>> = vec![];
+        for i in 0..10 {
+            for j in 0..10 {
+                let (x, y) = (i as f64 - 5.0, j as f64 - 5.0);
+                result.push(Box::new(Triangle::new(
+                    Tuple::point(x, y + 1.0, 0.1 * x),
+                    Tuple::point(x - 0.5, y, 0.1 * y),
+                    Tuple::point(x + 0.5, y, 0.0),
+                    Matrix4::identity(),
+                    Material::default(),
+                )));
+            }
+        }
+        result.push(Box::new(get_default_spheres()[0].clone()));
+        result
+    }
+
+    #[test]
+    fn test_bvh_matches_brute_force() {
+        let with_bvh = World::new(triangle_grid(), World::default().lights);
+        let mut brute_force = World::default();
+        brute_force.objects = triangle_grid();
+
+        for k in 0..50 {
+            let ray = Ray {
+                origin: Tuple::point(-3.0 + 0.13 * k as f64, 2.0, -5.0),
+                direction: Tuple::vector(0.02 * k as f64, -0.1, 1.0).normalize(),
+                time: 0.0,
+            };
+            let a: Vec<f64> = with_bvh.intersect(&ray).iter().map(|i| i.t).collect();
+            let b: Vec<f64> = brute_force.intersect(&ray).iter().map(|i| i.t).collect();
+            assert_eq!(a, b);
+            assert_eq!(with_bvh.color_at(&ray, 0), brute_force.color_at(&ray, 0));
+        }
+    }
 }